@@ -1,313 +1,1956 @@
-use crate::parsed::Parsed;
+use crate::{
+    diagnostic::{Diagnostic, Report, Span},
+    lint,
+    parsed::Parsed,
+    reader,
+    table::{CharClass, CharSet, Item, Level, Precedence, RuleId, RuleTable, Sequence},
+};
 use lazy_static::lazy_static;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt, fs,
+    path::Path,
+    sync::Arc,
+};
 
-const ROOT: &'static str = "root";
-const EMPTY: &'static str = "EMPTY";
-const ASCII_DIGIT: &'static str = "ASCII_DIGIT";
-const ASCII_ALPHA: &'static str = "ASCII_ALPHA";
-const ASCII_ALPHANUMERIC: &'static str = "ASCII_ALPHANUMERIC";
-const WHITESPACE: &'static str = "WHITESPACE";
+const ROOT: &str = "root";
+const UNKNOWN: &str = "unknown";
+const ERROR: &str = "error";
+const RECOVER: &str = "RECOVER";
+const SYNC: &str = "SYNC";
+const EMPTY: &str = "EMPTY";
+const ASCII_DIGIT: &str = "ASCII_DIGIT";
+const ASCII_ALPHA: &str = "ASCII_ALPHA";
+const ASCII_ALPHANUMERIC: &str = "ASCII_ALPHANUMERIC";
+const ALPHA: &str = "ALPHA";
+const ALPHANUMERIC: &str = "ALPHANUMERIC";
+const XID_START: &str = "XID_START";
+const XID_CONTINUE: &str = "XID_CONTINUE";
+const WHITESPACE: &str = "WHITESPACE";
+const COMMENT: &str = "COMMENT";
+const PRECEDENCE: &str = "PRECEDENCE";
+// nombre de las gramaticas que no se cargan de un archivo, en los informes
+const GRAMMAR: &str = "grammar";
 
 lazy_static! {
     // reglas internas que se pueden utilizar en la gramatica por simplicidad
-    static ref INTERNAL_RULE: HashMap<&'static str, fn(&str) -> bool> = {
-        let mut map: HashMap<&str, fn(&str) -> bool> = HashMap::new();
-        map.insert(ASCII_DIGIT, |s| s.chars().all(|c| c.is_ascii_digit()));
-        map.insert(ASCII_ALPHA, |s| s.chars().all(|c| c.is_ascii_alphabetic()));
-        map.insert(ASCII_ALPHANUMERIC, |s| {
-            s.chars().all(|c| c.is_ascii_alphanumeric())
-        });
-        map.insert(WHITESPACE, |s| s.chars().all(|c| c.is_whitespace()));
-        map.insert(EMPTY, |s| s.is_empty());
+    // nota: se evaluan caracter por caracter, por lo que aceptan cualquier texto UTF-8
+    static ref INTERNAL_RULE: HashMap<&'static str, CharClass> = {
+        let mut map = HashMap::new();
+        map.insert(ASCII_DIGIT, CharClass::new(|c| c.is_ascii_digit()));
+        map.insert(ASCII_ALPHA, CharClass::new(|c| c.is_ascii_alphabetic()));
+        map.insert(ASCII_ALPHANUMERIC, CharClass::new(|c| c.is_ascii_alphanumeric()));
+        map.insert(ALPHA, CharClass::new(char::is_alphabetic));
+        map.insert(ALPHANUMERIC, CharClass::new(char::is_alphanumeric));
+        map.insert(XID_START, CharClass::new(unicode_ident::is_xid_start));
+        map.insert(XID_CONTINUE, CharClass::new(unicode_ident::is_xid_continue));
+        map.insert(WHITESPACE, CharClass::new(char::is_whitespace));
+        map.insert(EMPTY, CharClass::new(|_| false));
         map
     };
 }
 
-#[derive(Debug)]
-pub struct Token<'a>(pub &'a str, pub ((usize, usize), Option<Vec<Token<'a>>>));
+// regla interna por su nombre, salvo `EMPTY` que no reconoce ningun caracter
+pub(crate) fn internal_class(name: &str) -> Option<(&'static str, CharClass)> {
+    INTERNAL_RULE
+        .get_key_value(name)
+        .filter(|(name, _)| **name != EMPTY)
+        .map(|(name, class)| (*name, *class))
+}
 
-#[derive(Debug)]
-pub struct Error<'a> {
-    pub message: &'a str,
+// nota: los hijos se comparten con `Arc` para que la memoizacion pueda reutilizar un
+// resultado sin copiar todo el subarbol
+#[derive(Debug, Clone)]
+pub struct Token<'a>(
+    pub &'a str,
+    pub ((usize, usize), Option<Arc<Vec<Token<'a>>>>),
+);
+
+// error de sintaxis: ademas del rango de la regla que fallo, guarda el punto mas lejano
+// al que se llego, lo que se esperaba encontrar ahi y lo que se encontro
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub message: String,
+    pub rule: String,
+    pub expected: Vec<String>,
+    pub found: String,
+    pub position: usize,
     pub first: usize,
     pub last: usize,
 }
 
+// estado de un analisis: los errores encontrados y el punto mas lejano donde fallo
+// alguna expresion, con lo que se esperaba encontrar en ese punto
+struct State<'a> {
+    input: &'a str,
+    errors: Vec<Error>,
+    furthest: usize,
+    // nota: se comparte con `Arc` para que guardarlo en la tabla de memoizacion no lo copie
+    expected: Arc<BTreeSet<&'a str>>,
+    // resultados ya calculados de cada regla en cada posicion, si se usa memoizacion
+    // nota: el resultado de una regla depende de si se analiza dentro de una regla atomica
+    memo: Option<HashMap<(RuleId, usize, bool), Memo<'a>>>,
+    // veces que se reutilizo un resultado de la tabla
+    hits: usize,
+    // resultado parcial de las reglas recursivas por la izquierda que se estan analizando
+    seeds: HashMap<(RuleId, usize, bool), Memo<'a>>,
+    // si se esta dentro de una regla atomica, donde no se saltan espacios ni comentarios
+    atomic: bool,
+    // mensaje de la gramatica con el que se reporta la ultima regla o grupo que fallo, y
+    // la posicion donde fallo
+    label: Option<(usize, &'a str)>,
+}
+
+// resultado de analizar una regla en una posicion, con todo lo necesario para repetir
+// sus efectos sin volver a analizarla
+struct Memo<'a> {
+    token: Token<'a>,
+    matched: bool,
+    end: usize,
+    errors: Vec<Error>,
+    failures: (usize, Arc<BTreeSet<&'a str>>),
+    label: Option<(usize, &'a str)>,
+}
+
+// uso de la tabla de memoizacion en un analisis
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoStats {
+    // resultados guardados, uno por cada regla y posicion analizadas
+    pub entries: usize,
+    // veces que se reutilizo un resultado en vez de volver a analizar la regla
+    pub hits: usize,
+    // memoria aproximada que ocupa la tabla
+    // nota: no incluye los subarboles, que se comparten con el arbol resultante
+    pub bytes: usize,
+}
+
+impl<'a> State<'a> {
+    // registra que en la posicion se esperaba algo que no se encontro
+    fn fail(&mut self, position: usize, expected: &'a str) {
+        if position > self.furthest {
+            self.furthest = position;
+            self.expected = Arc::default();
+        }
+
+        if position == self.furthest {
+            Arc::make_mut(&mut self.expected).insert(expected);
+        }
+    }
+
+    // empieza a registrar los fallos de una regla por separado
+    fn take(&mut self, position: usize) -> (usize, Arc<BTreeSet<&'a str>>) {
+        let furthest = std::mem::replace(&mut self.furthest, position);
+        (furthest, std::mem::take(&mut self.expected))
+    }
+
+    // vuelve a juntar los fallos de la regla con los que habia antes
+    fn restore(&mut self, (furthest, expected): (usize, Arc<BTreeSet<&'a str>>)) {
+        if furthest > self.furthest || (furthest == self.furthest && self.expected.is_empty()) {
+            self.furthest = furthest;
+            self.expected = expected;
+        } else if furthest == self.furthest && !expected.is_empty() {
+            Arc::make_mut(&mut self.expected).extend(expected.iter());
+        }
+    }
+
+    // describe lo que hay en la posicion para los mensajes de error
+    fn found(&self, position: usize) -> String {
+        let rest = &self.input[position..];
+
+        match rest.chars().next() {
+            None => "end of input".to_string(),
+            Some('\n') => "end of line".to_string(),
+            Some(c) if c.is_whitespace() => "whitespace".to_string(),
+            Some(c) if c.is_alphanumeric() => {
+                let word: String = rest.chars().take_while(|c| c.is_alphanumeric()).collect();
+                format!("`{}`", word)
+            }
+            Some(c) => format!("`{}`", c),
+        }
+    }
+}
+
+// nombre legible de una regla: `(expression)` y `expression*` se muestran como `expression`
+fn describe_rule(rule: &str) -> String {
+    rule.trim_matches(|c| c == '(' || c == ')')
+        .trim_end_matches(['*', '+'])
+        .replace('_', " ")
+}
+
+fn describe_internal_rule(rule: &'static str) -> &'static str {
+    match rule {
+        ASCII_DIGIT => "digit",
+        ASCII_ALPHA | ALPHA => "letter",
+        ASCII_ALPHANUMERIC | ALPHANUMERIC => "letter or digit",
+        XID_START => "identifier",
+        XID_CONTINUE => "identifier character",
+        _ => rule,
+    }
+}
+
+// escribe el conjunto como en la gramatica: `['a'..'z' '_']`
+fn describe_set(ranges: &[(char, char)], negated: bool) -> String {
+    let ranges: Vec<String> = ranges
+        .iter()
+        .map(|(first, last)| match first == last {
+            true => format!("{:?}", first),
+            false => format!("{:?}..{:?}", first, last),
+        })
+        .collect();
+
+    format!("[{}{}]", if negated { "^" } else { "" }, ranges.join(" "))
+}
+
+// une los elementos esperados: "`a`", "`a` or `b`", "`a`, `b` or `c`"
+fn join_expected(expected: &[String]) -> String {
+    match expected.split_last() {
+        None => "nothing".to_string(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+// nota: las expresiones guardan copias de la gramatica para que el analizador
+// no dependa de la vida del texto del que se cargo
 #[derive(Debug)]
-pub enum Expression<'a> {
-    Keyword(&'a str),
-    String(&'a str),
-    Rule(&'a str),
-    InternalRule(&'a str),
+pub enum Expression {
+    Keyword(String),
+    String(String),
+    Rule(String),
+    InternalRule(String),
+    // `a*`, `a+` y `a?`: la expresion se repite entre `min` y `max` veces
+    Repeat {
+        expression: Box<Expression>,
+        min: usize,
+        max: Option<usize>,
+    },
+    // `( a ~ b | c )`: opciones escritas dentro de una regla
+    Group(Vec<Vec<Expression>>),
+    // `['a'..'z' '_']` o `[^'"']`: un caracter que esta (o no esta) en los rangos
+    Set {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    // `&a` y `!a`: se cumple si la expresion se cumple (o no) en la posicion, sin avanzar
+    Predicate {
+        expression: Box<Expression>,
+        negated: bool,
+    },
+    // `@ "mensaje"`: mensaje con el que se reportan los errores de la opcion
+    // nota: solo puede ser el ultimo elemento de una opcion
+    Label(String),
+    // `PRECEDENCE(operando ; left "+" | "-" ; left "*" | "/")`: operandos unidos por
+    // operadores, con un nivel por cada grupo de operadores, del que une menos al que une mas
+    Precedence {
+        operand: Box<Expression>,
+        levels: Vec<(Fixity, Vec<Vec<Expression>>)>,
+    },
 }
 
-pub struct Analyzer<'a> {
-    pub initial_rule: &'a str,
-    pub grammar: HashMap<&'a str, Vec<Vec<Expression<'a>>>>,
+// como se aplican los operadores de un nivel de `PRECEDENCE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fixity {
+    // operadores binarios, `a - b - c` es `(a - b) - c`
+    Left,
+    // operadores binarios, `a ^ b ^ c` es `a ^ (b ^ c)`
+    Right,
+    // operadores antes del operando, como `-a`
+    Prefix,
+    // operadores despues del operando, como `a!`
+    Postfix,
 }
 
-impl<'a> Analyzer<'a> {
-    pub fn new(grammar: &'a str, initial_rule: &'a str) -> anyhow::Result<Self> {
-        let mut map = HashMap::new();
+impl Expression {
+    // una regla, una regla interna o una keyword segun su nombre
+    pub(crate) fn named(name: &str) -> Self {
+        if INTERNAL_RULE.contains_key(name) {
+            Expression::InternalRule(name.to_string())
+        } else if name.ends_with("_keyword") {
+            Expression::Keyword(name.to_string())
+        } else {
+            Expression::Rule(name.to_string())
+        }
+    }
 
-        // se lee la gramatica y se almacena en un hashmap
-        for (i, line) in grammar.lines().enumerate() {
-            // se ignora las lineas vacias que se usan para separar las reglas
-            if line.is_empty() {
-                continue;
+    // recorre la expresion y las que contiene
+    pub fn walk<'a>(&'a self, f: &mut impl FnMut(&'a Expression)) {
+        f(self);
+
+        match self {
+            Expression::Repeat { expression, .. } | Expression::Predicate { expression, .. } => {
+                expression.walk(f)
+            }
+            Expression::Group(options) => {
+                for expression in options.iter().flatten() {
+                    expression.walk(f);
+                }
             }
+            Expression::Precedence { operand, levels } => {
+                operand.walk(f);
+                for expression in levels
+                    .iter()
+                    .flat_map(|(_, operators)| operators.iter().flatten())
+                {
+                    expression.walk(f);
+                }
+            }
+            _ => {}
+        }
+    }
 
-            let mut parts = line.splitn(2, " = ");
-
-            if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
-                let mut options = Vec::new();
-
-                for option in value.split(" | ") {
-                    let mut expressions = Vec::new();
-
-                    for component in option.split(" ~ ") {
-                        let component = component.trim();
-
-                        if component.len() > 0 {
-                            // se valida si es un string, una regla, una regla interna o una keyword
-                            if component.starts_with("\"") {
-                                expressions
-                                    .push(Expression::String(&component[1..component.len() - 1]));
-                                continue;
-                            } else if INTERNAL_RULE.contains_key(component) {
-                                expressions.push(Expression::InternalRule(component));
-                                continue;
-                            } else {
-                                if component.ends_with("_keyword") {
-                                    expressions.push(Expression::Keyword(component));
-                                } else {
-                                    expressions.push(Expression::Rule(component));
-                                }
-                                continue;
-                            }
-                        }
+    // si la expresion se puede cumplir sin consumir texto, dadas las reglas que pueden
+    pub fn nullable(&self, rules: &HashSet<&str>) -> bool {
+        match self {
+            Expression::String(text) => text.is_empty(),
+            Expression::Keyword(_) | Expression::Set { .. } => false,
+            Expression::Rule(rule) => rules.contains(rule.as_str()),
+            Expression::InternalRule(rule) => rule == EMPTY,
+            Expression::Repeat {
+                expression, min, ..
+            } => *min == 0 || expression.nullable(rules),
+            Expression::Group(options) => options
+                .iter()
+                .any(|option| option.iter().all(|expression| expression.nullable(rules))),
+            Expression::Predicate { .. } | Expression::Label(_) => true,
+            Expression::Precedence { operand, .. } => operand.nullable(rules),
+        }
+    }
 
-                        return Err(anyhow::anyhow!("Invalid grammar at line {}", i + 1));
+    // agrega las reglas que la opcion puede llamar sin haber consumido texto: las de la
+    // primera expresion y las de las siguientes mientras las anteriores puedan no consumir
+    pub fn left_calls<'a>(
+        option: &'a [Expression],
+        rules: &HashSet<&str>,
+        calls: &mut Vec<&'a str>,
+    ) {
+        for expression in option {
+            match expression {
+                Expression::Rule(rule) => calls.push(rule),
+                Expression::Repeat { expression, .. }
+                | Expression::Predicate { expression, .. } => {
+                    Self::left_calls(std::slice::from_ref(expression), rules, calls)
+                }
+                Expression::Group(options) => {
+                    for option in options {
+                        Self::left_calls(option, rules, calls);
+                    }
+                }
+                // la expresion puede empezar por el operando o por un operador prefijo
+                Expression::Precedence { operand, levels } => {
+                    Self::left_calls(std::slice::from_ref(operand), rules, calls);
+                    for (_, operators) in levels
+                        .iter()
+                        .filter(|(fixity, _)| *fixity == Fixity::Prefix)
+                    {
+                        for option in operators {
+                            Self::left_calls(option, rules, calls);
+                        }
                     }
+                }
+                _ => {}
+            }
+
+            if !expression.nullable(rules) {
+                break;
+            }
+        }
+    }
+
+    // si la opcion se puede cumplir sin llamar a las reglas del ciclo antes de consumir
+    // texto, con lo que el ciclo tiene un resultado inicial desde el que crecer
+    pub fn escapes(option: &[Expression], cycle: &HashSet<&str>, rules: &HashSet<&str>) -> bool {
+        for expression in option {
+            let escapes = match expression {
+                Expression::Rule(rule) => !cycle.contains(rule.as_str()),
+                Expression::Repeat {
+                    expression, min, ..
+                } => *min == 0 || Self::escapes(std::slice::from_ref(expression), cycle, rules),
+                Expression::Predicate { expression, .. } => {
+                    Self::escapes(std::slice::from_ref(expression), cycle, rules)
+                }
+                Expression::Group(options) => options
+                    .iter()
+                    .any(|option| Self::escapes(option, cycle, rules)),
+                Expression::Precedence { operand, .. } => {
+                    Self::escapes(std::slice::from_ref(operand), cycle, rules)
+                }
+                _ => true,
+            };
+
+            if !escapes {
+                return false;
+            }
+            if !expression.nullable(rules) {
+                return true;
+            }
+        }
+
+        true
+    }
+}
 
-                    options.push(expressions);
+// escribe la expresion como en la gramatica, para los mensajes de la revision
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Keyword(name) | Expression::Rule(name) | Expression::InternalRule(name) => {
+                write!(f, "{}", name)
+            }
+            Expression::String(text) => write!(f, "{:?}", text),
+            Expression::Repeat {
+                expression,
+                min,
+                max,
+            } => {
+                let operator = match (min, max) {
+                    (0, Some(1)) => "?",
+                    (0, _) => "*",
+                    _ => "+",
+                };
+                write!(f, "{}{}", expression, operator)
+            }
+            Expression::Group(options) => write!(f, "({})", describe_options(options)),
+            Expression::Set { ranges, negated } => write!(f, "{}", describe_set(ranges, *negated)),
+            Expression::Predicate {
+                expression,
+                negated,
+            } => write!(f, "{}{}", if *negated { "!" } else { "&" }, expression),
+            Expression::Label(label) => write!(f, "@ {:?}", label),
+            Expression::Precedence { operand, levels } => {
+                write!(f, "{}({}", PRECEDENCE, operand)?;
+                for (fixity, operators) in levels {
+                    let fixity = match fixity {
+                        Fixity::Left => "left",
+                        Fixity::Right => "right",
+                        Fixity::Prefix => "prefix",
+                        Fixity::Postfix => "postfix",
+                    };
+                    write!(f, " ; {} {}", fixity, describe_options(operators))?;
                 }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+// escribe las opciones de un grupo: `a ~ b | c`
+// nota: el mensaje de la opcion va al final, separado por un espacio
+fn describe_options(options: &[Vec<Expression>]) -> String {
+    let options: Vec<String> = options
+        .iter()
+        .map(|option| {
+            let expressions: Vec<String> = option
+                .iter()
+                .filter(|expression| !matches!(expression, Expression::Label(_)))
+                .map(Expression::to_string)
+                .collect();
+            let label = option
+                .iter()
+                .find(|expression| matches!(expression, Expression::Label(_)));
+
+            match label {
+                Some(label) => format!("{} {}", expressions.join(" ~ "), label),
+                None => expressions.join(" ~ "),
+            }
+        })
+        .collect();
+
+    options.join(" | ")
+}
 
-                map.insert(key, options);
+// configuracion de la recuperacion de errores: cuando una de las reglas falla, el
+// analizador salta hasta un punto de sincronizacion y continua desde ahi
+// nota: se define en la gramatica con las reglas especiales RECOVER y SYNC, por ejemplo
+// `RECOVER = instruction` y `SYNC = ";" | "{" ~ "}"`
+#[derive(Debug, Default)]
+pub struct Recovery {
+    // reglas que se recuperan de sus errores
+    pub rules: HashSet<String>,
+    // textos que terminan una regla, como `;`
+    pub terminators: Vec<String>,
+    // pares que abren y cierran un bloque, como `{` y `}`
+    pub pairs: Vec<(String, String)>,
+}
+
+impl Recovery {
+    pub fn is_enabled(&self) -> bool {
+        !self.terminators.is_empty() || !self.pairs.is_empty()
+    }
+
+    // si el texto reconocido ya termina en un punto de sincronizacion no hace falta saltar
+    fn is_synchronized(&self, text: &str) -> bool {
+        self.depth(text) == 0
+            && (self.terminators.iter().any(|t| text.ends_with(t.as_str()))
+                || self
+                    .pairs
+                    .iter()
+                    .any(|(_, close)| text.ends_with(close.as_str())))
+    }
+
+    // bloques que quedan abiertos en el texto
+    fn depth(&self, text: &str) -> usize {
+        let mut depth = 0usize;
+        let mut rest = text;
+
+        while let Some(c) = rest.chars().next() {
+            if let Some((open, _)) = self
+                .pairs
+                .iter()
+                .find(|(open, _)| rest.starts_with(open.as_str()))
+            {
+                depth += 1;
+                rest = &rest[open.len()..];
+            } else if let Some((_, close)) = self
+                .pairs
+                .iter()
+                .find(|(_, close)| rest.starts_with(close.as_str()))
+            {
+                depth = depth.saturating_sub(1);
+                rest = &rest[close.len()..];
             } else {
-                return Err(anyhow::anyhow!("Invalid grammar at line {}", i + 1));
+                rest = &rest[c.len_utf8()..];
             }
         }
 
-        Ok(Self {
+        depth
+    }
+
+    // devuelve hasta donde se salta desde la posicion: despues de un terminador que no
+    // este dentro de un bloque, despues del cierre del bloque que quedo abierto o antes
+    // de un cierre que le pertenece a un bloque anterior
+    fn skip(&self, input: &str, position: usize, mut depth: usize) -> usize {
+        let mut end = position;
+
+        while let Some(c) = input[end..].chars().next() {
+            let rest = &input[end..];
+
+            if let Some((open, _)) = self
+                .pairs
+                .iter()
+                .find(|(open, _)| rest.starts_with(open.as_str()))
+            {
+                depth += 1;
+                end += open.len();
+            } else if let Some((_, close)) = self
+                .pairs
+                .iter()
+                .find(|(_, close)| rest.starts_with(close.as_str()))
+            {
+                if depth == 0 {
+                    return end;
+                }
+
+                depth -= 1;
+                end += close.len();
+
+                if depth == 0 {
+                    return end;
+                }
+            } else if let Some(terminator) = self
+                .terminators
+                .iter()
+                .find(|t| rest.starts_with(t.as_str()))
+            {
+                end += terminator.len();
+
+                if depth == 0 {
+                    return end;
+                }
+            } else {
+                end += c.len_utf8();
+            }
+        }
+
+        end
+    }
+}
+
+// modificador escrito alrededor de las opciones de una regla, como `ident = @{ ... }`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    // `_{ ... }`: la regla no aparece en el arbol, sus tokens quedan en la regla que la usa
+    Silent,
+    // `@{ ... }`: no se saltan espacios ni comentarios dentro de la regla ni de las reglas
+    // que usa, y la regla queda en el arbol como una sola hoja
+    Atomic,
+    // `${ ... }`: como `@`, pero las reglas que usa quedan en el arbol
+    CompoundAtomic,
+}
+
+// resultado parcial de una expresion con operadores
+struct Operation<'a> {
+    tokens: Vec<Token<'a>>,
+    first: usize,
+    last: usize,
+    // si es una operacion, que queda como un nodo propio al usarla como operando de otra, o
+    // un operando solo
+    nested: bool,
+}
+
+impl<'a> Operation<'a> {
+    fn new(tokens: Vec<Token<'a>>, first: usize, last: usize) -> Self {
+        Operation {
+            tokens,
+            first,
+            last,
+            nested: true,
+        }
+    }
+
+    // tokens de la expresion como operando de otra operacion
+    // nota: los nodos de las operaciones llevan el nombre de la regla que las contiene
+    fn into_tokens(self, rule: &'a str) -> Vec<Token<'a>> {
+        if self.nested {
+            vec![Token(
+                rule,
+                ((self.first, self.last), Some(Arc::new(self.tokens))),
+            )]
+        } else {
+            self.tokens
+        }
+    }
+}
+
+// reglas con recursion por la izquierda
+pub(crate) struct LeftRecursion<'a> {
+    // reglas que se llaman a si mismas sin consumir texto
+    rules: HashSet<&'a str>,
+    // una regla de cada ciclo, que se analiza haciendo crecer su resultado mientras las
+    // demas reglas del ciclo usan el resultado que tiene hasta el momento
+    heads: HashSet<&'a str>,
+    // el primer camino de llamadas encontrado desde cada una de esas reglas hasta ella
+    // misma, para los informes
+    cycles: Vec<Vec<&'a str>>,
+    // reglas que llama cada regla sin haber consumido texto
+    pub(crate) calls: HashMap<&'a str, Vec<&'a str>>,
+}
+
+// donde termina el ultimo token del arbol que no es un espacio ni un comentario
+fn last_content(token: &Token) -> usize {
+    let Token(_, ((start, end), children)) = token;
+    let Some(children) = children else {
+        return *end;
+    };
+
+    children
+        .iter()
+        .rev()
+        .filter(|child| ![WHITESPACE, COMMENT].contains(&child.0) && child.1 .0 .1 > child.1 .0 .0)
+        .map(last_content)
+        .find(|end| end > start)
+        .unwrap_or(*start)
+}
+
+// reglas a las que se llega desde la regla siguiendo sus llamadas
+pub(crate) fn reachable<'a>(
+    calls: &HashMap<&'a str, Vec<&'a str>>,
+    rule: &str,
+) -> HashSet<&'a str> {
+    let mut pending = calls.get(rule).cloned().unwrap_or_default();
+    let mut visited = HashSet::new();
+    while let Some(next) = pending.pop() {
+        if visited.insert(next) {
+            pending.extend(calls.get(next).into_iter().flatten());
+        }
+    }
+    visited
+}
+
+pub struct Analyzer {
+    // nombre y texto de la gramatica, para ubicar sus problemas en los informes
+    pub name: String,
+    pub source: String,
+    pub initial_rule: String,
+    pub grammar: HashMap<String, Vec<Vec<Expression>>>,
+    pub recovery: Recovery,
+    // modificadores de las reglas que tienen alguno
+    pub modifiers: HashMap<String, Modifier>,
+    // guarda el resultado de cada regla en cada posicion para no repetir el analisis
+    // cuando una alternativa falla y se prueba la siguiente
+    pub memoize: bool,
+    // la gramatica compilada con la que se analiza
+    // nota: se construye en `new`, por lo que cambiar `grammar` despues no la modifica
+    table: RuleTable,
+    // texto de la definicion de cada regla en `source`
+    spans: HashMap<String, Span>,
+}
+
+impl Analyzer {
+    // carga la gramatica desde un archivo en tiempo de ejecucion
+    pub fn from_file<P: AsRef<Path>>(path: P, initial_rule: &str) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let grammar = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read grammar {}: {}", path.display(), e))?;
+        Self::load(&grammar, initial_rule, &path.display().to_string())
+    }
+
+    pub fn new(grammar: &str, initial_rule: &str) -> anyhow::Result<Self> {
+        Self::load(grammar, initial_rule, GRAMMAR)
+    }
+
+    // nota: se leen todas las reglas aunque alguna sea invalida, para reportar juntos
+    // todos los problemas de la gramatica
+    fn load(grammar: &str, initial_rule: &str, name: &str) -> anyhow::Result<Self> {
+        let mut map = HashMap::new();
+        let mut modifiers = HashMap::new();
+        let mut spans = HashMap::new();
+
+        let (definitions, mut diagnostics) = reader::read(grammar);
+        for definition in definitions {
+            // se usa la primera definicion de cada regla
+            let span = |(start, _): Span| (start, start + definition.name.len());
+            if let Some(first) = spans.get(&definition.name) {
+                diagnostics.push(
+                    Diagnostic::error(
+                        format!("Duplicate rule: {}", definition.name),
+                        Some(span(definition.span)),
+                    )
+                    .with_note("first defined here".to_string(), span(*first)),
+                );
+                continue;
+            }
+
+            if let Some(modifier) = definition.modifier {
+                modifiers.insert(definition.name.clone(), modifier);
+            }
+            spans.insert(definition.name.clone(), definition.span);
+            map.insert(definition.name, definition.options);
+        }
+
+        let recovery = Self::recovery(&mut map, &spans, &mut diagnostics);
+        let table = Self::compile(&map, &recovery, &modifiers, initial_rule);
+
+        let analyzer = Self {
+            name: name.to_string(),
+            source: grammar.to_string(),
             grammar: map,
-            initial_rule,
-        })
+            initial_rule: initial_rule.to_string(),
+            recovery,
+            modifiers,
+            memoize: false,
+            table,
+            spans,
+        };
+
+        // si la gramatica no se pudo leer entera tambien se revisan las reglas leidas, para
+        // reportar todos los problemas juntos
+        if diagnostics.iter().any(Diagnostic::is_error) {
+            diagnostics.extend(analyzer.validation());
+            let mut report = Report::default();
+            report.add(&diagnostics, name, grammar);
+            return Err(anyhow::anyhow!(
+                "Invalid grammar:\n{}",
+                report.text.trim_end()
+            ));
+        }
+
+        Ok(analyzer)
+    }
+
+    // traduce la gramatica a la tabla de reglas: cada regla recibe un numero y cada
+    // expresion se resuelve de antemano (el texto de las keywords, los caracteres de las
+    // reglas internas y lo que se reporta si no se encuentran)
+    fn compile(
+        map: &HashMap<String, Vec<Vec<Expression>>>,
+        recovery: &Recovery,
+        modifiers: &HashMap<String, Modifier>,
+        initial_rule: &str,
+    ) -> RuleTable {
+        let mut table = RuleTable::default();
+        let left_recursion = Self::left_recursion(map);
+
+        // se numeran las reglas en orden para que la tabla no dependa del hashmap
+        let mut names: Vec<&String> = map.keys().collect();
+        names.sort();
+        for name in names {
+            table.insert(name, describe_rule(name));
+        }
+        table.initial = table.insert(initial_rule, describe_rule(initial_rule));
+
+        for (name, options) in map {
+            let options = Self::compile_options(map, &mut table, options);
+            let id = table.insert(name, describe_rule(name));
+            table.rules[id].options = options;
+            table.rules[id].recover = recovery.rules.contains(name);
+            table.rules[id].left_recursive = left_recursion.rules.contains(name.as_str());
+            table.rules[id].grows = left_recursion.heads.contains(name.as_str());
+            let modifier = modifiers.get(name);
+            table.rules[id].silent = modifier == Some(&Modifier::Silent);
+            table.rules[id].leaf = modifier == Some(&Modifier::Atomic);
+            table.rules[id].atomic =
+                table.rules[id].leaf || modifier == Some(&Modifier::CompoundAtomic);
+        }
+
+        // si la gramatica define `WHITESPACE` o `COMMENT` se saltan automaticamente entre
+        // las expresiones, como en pest
+        // nota: se analizan como reglas atomicas, de lo contrario se saltarian a si mismas, y
+        // quedan como una sola hoja salvo que sean silenciosas
+        for name in [WHITESPACE, COMMENT] {
+            if map.contains_key(name) {
+                let id = table.insert(name, describe_rule(name));
+                table.rules[id].atomic = true;
+                table.rules[id].leaf = !table.rules[id].silent;
+                table.trivia.push(id);
+            }
+        }
+
+        table
+    }
+
+    // nota: el mensaje de una opcion se guarda aparte de sus expresiones
+    fn compile_options(
+        map: &HashMap<String, Vec<Vec<Expression>>>,
+        table: &mut RuleTable,
+        options: &[Vec<Expression>],
+    ) -> Vec<Sequence> {
+        options
+            .iter()
+            .map(|option| Sequence {
+                items: option
+                    .iter()
+                    .filter(|expression| !matches!(expression, Expression::Label(_)))
+                    .map(|expression| Self::compile_expression(map, table, expression))
+                    .collect(),
+                label: option.iter().find_map(|expression| match expression {
+                    Expression::Label(label) => Some(label.clone()),
+                    _ => None,
+                }),
+            })
+            .collect()
+    }
+
+    fn compile_expression(
+        map: &HashMap<String, Vec<Vec<Expression>>>,
+        table: &mut RuleTable,
+        expression: &Expression,
+    ) -> Item {
+        match expression {
+            Expression::String(text) => Item::String {
+                text: text.clone(),
+                expected: format!("`{}`", text),
+            },
+            Expression::Keyword(rule) => match map
+                .get(rule)
+                .and_then(|options| options.first())
+                .and_then(|option| option.first())
+            {
+                Some(Expression::String(text)) => Item::Keyword {
+                    text: text.clone(),
+                    expected: format!("`{}`", text),
+                    word: text
+                        .chars()
+                        .last()
+                        .is_some_and(unicode_ident::is_xid_continue),
+                },
+                // las keywords invalidas no consumen texto, `validate` las reporta
+                _ => Item::Empty,
+            },
+            Expression::Rule(rule) => Item::Rule(table.insert(rule, describe_rule(rule))),
+            Expression::InternalRule(rule) => match INTERNAL_RULE.get_key_value(rule.as_str()) {
+                Some((&rule, class)) if rule != EMPTY => Item::Class {
+                    class: *class,
+                    // los espacios nunca son lo que el usuario esperaba escribir
+                    expected: (rule != WHITESPACE).then(|| describe_internal_rule(rule)),
+                },
+                _ => Item::Empty,
+            },
+            Expression::Repeat {
+                expression,
+                min,
+                max,
+            } => Item::Repeat {
+                item: Box::new(Self::compile_expression(map, table, expression)),
+                min: *min,
+                max: *max,
+            },
+            Expression::Predicate {
+                expression,
+                negated,
+            } => Item::Predicate {
+                item: Box::new(Self::compile_expression(map, table, expression)),
+                negated: *negated,
+            },
+            Expression::Set { ranges, negated } => Item::Set {
+                set: CharSet::new(ranges.clone(), *negated),
+                expected: format!("`{}`", describe_set(ranges, *negated)),
+            },
+            Expression::Group(options) => Item::Group(Self::compile_options(map, table, options)),
+            // los mensajes se guardan en la opcion al compilarla
+            Expression::Label(_) => Item::Empty,
+            // los operadores prefijos y posfijos unen mas que los binarios, por lo que no
+            // forman niveles
+            Expression::Precedence { operand, levels } => {
+                let mut precedence = Precedence {
+                    operand: Self::compile_expression(map, table, operand),
+                    levels: Vec::new(),
+                    prefix: Vec::new(),
+                    postfix: Vec::new(),
+                };
+
+                for (fixity, operators) in levels {
+                    let operators = Self::compile_options(map, table, operators);
+                    match fixity {
+                        Fixity::Left | Fixity::Right => precedence.levels.push(Level {
+                            operators,
+                            right: *fixity == Fixity::Right,
+                        }),
+                        Fixity::Prefix => precedence.prefix.extend(operators),
+                        Fixity::Postfix => precedence.postfix.extend(operators),
+                    }
+                }
+
+                Item::Precedence(Box::new(precedence))
+            }
+        }
+    }
+
+    // extrae de la gramatica las reglas especiales de la recuperacion de errores
+    fn recovery(
+        map: &mut HashMap<String, Vec<Vec<Expression>>>,
+        spans: &HashMap<String, Span>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Recovery {
+        let mut recovery = Recovery::default();
+        let span = |rule: &str| {
+            spans
+                .get(rule)
+                .map(|(start, _)| (*start, start + rule.len()))
+        };
+
+        for option in map.remove(RECOVER).unwrap_or_default() {
+            match option.as_slice() {
+                [Expression::Rule(rule)] => {
+                    recovery.rules.insert(rule.clone());
+                }
+                _ => diagnostics.push(Diagnostic::error(
+                    format!("{} must list rule names", RECOVER),
+                    span(RECOVER),
+                )),
+            }
+        }
+
+        for option in map.remove(SYNC).unwrap_or_default() {
+            match option.as_slice() {
+                [Expression::String(terminator)] => recovery.terminators.push(terminator.clone()),
+                [Expression::String(open), Expression::String(close)] => {
+                    recovery.pairs.push((open.clone(), close.clone()))
+                }
+                _ => diagnostics.push(Diagnostic::error(
+                    format!("{} must list strings or pairs of strings", SYNC),
+                    span(SYNC),
+                )),
+            }
+        }
+
+        recovery
     }
 
-    // se usa en el desarrollo para validar la gramatica
+    // se usa en el desarrollo para validar la gramatica: falla con el informe de los
+    // errores, sin las advertencias
     pub fn validate(&self) -> anyhow::Result<()> {
+        let errors: Vec<Diagnostic> = self
+            .validation()
+            .into_iter()
+            .filter(Diagnostic::is_error)
+            .collect();
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        let mut report = Report::default();
+        report.add(&errors, &self.name, &self.source);
+        Err(anyhow::anyhow!(
+            "Invalid grammar:\n{}",
+            report.text.trim_end()
+        ))
+    }
+
+    // todos los problemas de la gramatica: los de la validacion y las advertencias de la
+    // revision de las opciones, que no impiden usarla
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.validation();
+        diagnostics.extend(lint::lint(&self.grammar, &|rule| self.rule_span(rule)));
+        diagnostics
+    }
+
+    fn validation(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
         let mut visited = HashSet::new();
 
-        for (_, options) in self.grammar.iter() {
-            for option in options {
-                for expression in option {
-                    // se valida que las reglas existan
-                    match expression {
-                        Expression::Rule(rule) | Expression::Keyword(rule) => {
-                            visited.insert(rule);
-
-                            if !self.grammar.contains_key(rule) {
-                                return Err(anyhow::anyhow!("Invalid rule: {}", rule));
-                            }
-                        }
-                        Expression::InternalRule(rule) => {
-                            if !INTERNAL_RULE.contains_key(rule) {
-                                return Err(anyhow::anyhow!("Invalid internal rule: {}", rule));
-                            }
+        if !self.grammar.contains_key(&self.initial_rule) {
+            diagnostics.push(Diagnostic::error(
+                format!("Undefined initial rule: {}", self.initial_rule),
+                None,
+            ));
+        }
+
+        for (name, options) in &self.grammar {
+            // se recorren tambien las expresiones que estan dentro de las repeticiones
+            let mut expressions = Vec::new();
+            for expression in options.iter().flatten() {
+                expression.walk(&mut |expression| expressions.push(expression));
+            }
+
+            for expression in expressions {
+                // se valida que las reglas existan
+                match expression {
+                    Expression::Rule(rule) | Expression::Keyword(rule) => {
+                        visited.insert(rule);
+
+                        if !self.grammar.contains_key(rule) {
+                            diagnostics.push(Diagnostic::error(
+                                format!("Invalid rule: {}", rule),
+                                self.reference_span(name, rule),
+                            ));
                         }
-                        _ => {}
                     }
+                    Expression::InternalRule(rule)
+                        if !INTERNAL_RULE.contains_key(rule.as_str()) =>
+                    {
+                        diagnostics.push(Diagnostic::error(
+                            format!("Invalid internal rule: {}", rule),
+                            self.reference_span(name, rule),
+                        ));
+                    }
+                    _ => {}
                 }
             }
         }
 
+        // se valida que existan las reglas que se recuperan de errores
+        for rule in &self.recovery.rules {
+            if !self.grammar.contains_key(rule) {
+                diagnostics.push(Diagnostic::error(
+                    format!("Invalid rule in {}: {}", RECOVER, rule),
+                    self.reference_span(RECOVER, rule),
+                ));
+            }
+        }
+
+        // se validan los ciclos de recursion por la izquierda: crecen desde el resultado de
+        // una opcion que no vuelve al ciclo, y si ninguna regla del ciclo tiene una, ninguna
+        // se puede cumplir
+        let recursion = Self::left_recursion(&self.grammar);
+        let nullable = Self::nullable_rules(&self.grammar);
+        for path in &recursion.cycles {
+            let head = path[0];
+            let cycle: HashSet<&str> = reachable(&recursion.calls, head)
+                .into_iter()
+                .filter(|rule| reachable(&recursion.calls, rule).contains(head))
+                .collect();
+            let base = cycle.iter().any(|rule| {
+                self.grammar.get(*rule).is_some_and(|options| {
+                    options
+                        .iter()
+                        .any(|option| Expression::escapes(option, &cycle, &nullable))
+                })
+            });
+
+            if !base {
+                diagnostics.push(Diagnostic::error(
+                    format!(
+                        "Left recursion without a non-recursive option: {}",
+                        path.join(" -> ")
+                    ),
+                    self.rule_span(head),
+                ));
+            }
+        }
+
         for rule in self.grammar.keys() {
             // se valida que todas las reglas sean usadas
-            if !visited.contains(rule) && rule != &self.initial_rule {
-                return Err(anyhow::anyhow!("Unused rule: {}", rule));
+            // nota: `WHITESPACE` y `COMMENT` se usan entre las expresiones sin nombrarlas
+            if !visited.contains(rule)
+                && rule != &self.initial_rule
+                && rule != WHITESPACE
+                && rule != COMMENT
+            {
+                diagnostics.push(Diagnostic::warning(
+                    format!("Unused rule: {}", rule),
+                    self.rule_span(rule),
+                ));
             }
 
             // se valida que las reglas keyword tengan un solo string
+            // nota: las reglas que no se pudieron leer quedan sin opciones y ya se reportaron
             if rule.ends_with("_keyword")
+                && !self.grammar[rule].is_empty()
                 && (self.grammar[rule].len() != 1 || self.grammar[rule][0].len() != 1)
             {
-                return Err(anyhow::anyhow!("Invalid keyword rule: {}", rule));
+                diagnostics.push(Diagnostic::error(
+                    format!("Invalid keyword rule: {}", rule),
+                    self.rule_span(rule),
+                ));
             }
         }
 
-        Ok(())
+        diagnostics
+    }
+
+    // nombre de la regla en su definicion
+    fn rule_span(&self, rule: &str) -> Option<Span> {
+        self.spans
+            .get(rule)
+            .map(|(start, _)| (*start, start + rule.len()))
+    }
+
+    // primer uso de `name` como palabra en las opciones de la regla, o el nombre de la
+    // regla si no se encuentra
+    fn reference_span(&self, rule: &str, name: &str) -> Option<Span> {
+        let (start, end) = *self.spans.get(rule)?;
+        let definition = &self.source[start..end];
+        let is_word = |c: Option<char>| c.is_some_and(unicode_ident::is_xid_continue);
+
+        definition
+            .match_indices(name)
+            .map(|(i, _)| i)
+            .filter(|i| *i >= rule.len())
+            .find(|i| {
+                !is_word(definition[..*i].chars().next_back())
+                    && !is_word(definition[i + name.len()..].chars().next())
+            })
+            .map(|i| (start + i, start + i + name.len()))
+            .or_else(|| self.rule_span(rule))
+    }
+
+    // reglas que se pueden cumplir sin consumir texto
+    // nota: se agregan reglas hasta que ninguna opcion cambia de resultado
+    pub(crate) fn nullable_rules(map: &HashMap<String, Vec<Vec<Expression>>>) -> HashSet<&str> {
+        let mut nullable = HashSet::new();
+
+        loop {
+            let length = nullable.len();
+            for (name, options) in map {
+                if options.iter().any(|option| {
+                    option
+                        .iter()
+                        .all(|expression| expression.nullable(&nullable))
+                }) {
+                    nullable.insert(name.as_str());
+                }
+            }
+
+            if nullable.len() == length {
+                return nullable;
+            }
+        }
+    }
+
+    // busca las reglas que se llaman a si mismas, directamente o a traves de otras, sin
+    // consumir texto
+    pub(crate) fn left_recursion(map: &HashMap<String, Vec<Vec<Expression>>>) -> LeftRecursion<'_> {
+        let nullable = Self::nullable_rules(map);
+
+        let mut calls: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (name, options) in map {
+            let calls = calls.entry(name).or_default();
+            for option in options {
+                Expression::left_calls(option, &nullable, calls);
+            }
+        }
+
+        // una regla es recursiva si desde sus llamadas se vuelve a llegar a ella
+        let rules = calls
+            .keys()
+            .copied()
+            .filter(|rule| reachable(&calls, rule).contains(rule))
+            .collect();
+
+        // recorrido en profundidad: cada vez que se vuelve a una regla del camino actual se
+        // cierra un ciclo, y esa regla es la que hace crecer su resultado
+        // nota: quitar esas llamadas deja un grafo sin ciclos, asi que todo ciclo tiene una
+        fn visit<'a>(
+            rule: &'a str,
+            calls: &HashMap<&'a str, Vec<&'a str>>,
+            path: &mut Vec<&'a str>,
+            visited: &mut HashSet<&'a str>,
+            heads: &mut HashSet<&'a str>,
+            cycles: &mut Vec<Vec<&'a str>>,
+        ) {
+            if let Some(i) = path.iter().position(|other| *other == rule) {
+                if heads.insert(rule) {
+                    let mut cycle = path[i..].to_vec();
+                    cycle.push(rule);
+                    cycles.push(cycle);
+                }
+                return;
+            }
+
+            if !visited.insert(rule) {
+                return;
+            }
+
+            path.push(rule);
+            for next in calls.get(rule).into_iter().flatten() {
+                visit(next, calls, path, visited, heads, cycles);
+            }
+            path.pop();
+        }
+
+        // se recorren las reglas en orden para elegir siempre las mismas
+        let mut names: Vec<&str> = calls.keys().copied().collect();
+        names.sort();
+
+        let (mut visited, mut heads, mut cycles) = (HashSet::new(), HashSet::new(), Vec::new());
+        for name in names {
+            let path = &mut Vec::new();
+            visit(name, &calls, path, &mut visited, &mut heads, &mut cycles);
+        }
+
+        LeftRecursion {
+            rules,
+            heads,
+            cycles,
+            calls,
+        }
     }
 
-    pub fn parse(&self, input: &'a str) -> Parsed {
+    pub fn parse<'a>(&'a self, input: &'a str) -> Parsed<'a> {
+        self.parse_with_stats(input).0
+    }
+
+    // analiza el input y devuelve ademas el uso de la tabla de memoizacion
+    pub fn parse_with_stats<'a>(&'a self, input: &'a str) -> (Parsed<'a>, MemoStats) {
         let mut position = 0;
-        let mut errors = Vec::new();
         let mut tokens = Vec::new();
+        let mut state = State {
+            input,
+            errors: Vec::new(),
+            furthest: 0,
+            expected: Arc::default(),
+            memo: self.memoize.then(HashMap::new),
+            hits: 0,
+            seeds: HashMap::new(),
+            atomic: false,
+            label: None,
+        };
 
         // iterar sobre el input, si no se ha llegado al final se intenta parsear lo demas
         // nota: si no se llega al final del input, se asume que hay un error de sintaxis
         while position < input.len() {
             let mut tmp = position;
+            // los fallos de cada intento se registran aparte para reportarlos si se recupera
+            let saved = state.take(position);
+            // los espacios y comentarios entre las instrucciones se saltan antes de analizarlas
+            self.skip_trivia(&mut tmp, &mut tokens, &mut state);
             // se parsea el input
-            let mut token = self.resursive_parse(self.initial_rule, &mut tmp, &mut errors, input);
+            // nota: una regla inicial recursiva por la izquierda debe crecer como las demas
+            let (token, _) = if self.table.rules[self.table.initial].left_recursive {
+                self.resursive_parse(self.table.initial, &mut tmp, &mut state)
+            } else {
+                self.parse_options(self.table.initial, &mut tmp, &mut state)
+            };
 
-            if tmp + 1 < input.len() {
-                if let Some(children) = &mut token.1 .1 {
-                    // se tiene que adaptar a la regla a la que probablemente pertenece
-                    children.push(Token("unknown", ((tmp, tmp + 1), None)));
-                }    
+            // se omiten los tokens vacios, no aportan texto al arbol
+            if token.1 .0 .0 < token.1 .0 .1 {
+                self.push_token(self.table.initial, token, &mut tokens);
+            }
+
+            // los espacios donde se detuvo el analisis no son un error, se conservan como tales
+            let spaces = input[tmp..]
+                .chars()
+                .take_while(|c| c.is_whitespace())
+                .map(char::len_utf8)
+                .sum::<usize>();
+
+            if spaces > 0 {
+                tokens.push(Token(WHITESPACE, ((tmp, tmp + spaces), None)));
+                tmp += spaces;
+            } else if tmp < input.len() {
+                if self.recovery.is_enabled() {
+                    // se salta hasta el siguiente punto de sincronizacion y el texto saltado
+                    // se guarda como un nodo de error con un solo mensaje
+                    let end = self.recovery.skip(input, tmp, 0);
+                    let end = end.max(tmp + input[tmp..].chars().next().map_or(1, char::len_utf8));
+                    // nota: si la regla que se recupero ya reporto el error en este punto (por
+                    // ejemplo la `}` que sobra en `var y = 2 }`), el texto se salta sin
+                    // reportarlo otra vez
+                    let reported = state.errors.last().is_some_and(|e| e.position == tmp);
+                    if !reported {
+                        let error = self.error(&self.initial_rule, None, (tmp, end), &[], &state);
+                        state.errors.push(error);
+                    }
+                    tokens.push(Token(ERROR, ((tmp, end), None)));
+                    tmp = end;
+                } else {
+                    // el caracter donde se detuvo el analisis se guarda como token de error
+                    // para que las hojas del arbol cubran toda la entrada
+                    // nota: se reporta un error por cada tramo de caracteres desconocidos, con
+                    // lo que se esperaba en el punto mas lejano de los intentos anteriores
+                    // nota: si ya se reporto un error desde donde se detuvo el analisis, el
+                    // caracter no se reporta otra vez
+                    let end = tmp + input[tmp..].chars().next().map_or(1, char::len_utf8);
+                    let reported = state.errors.last().is_some_and(|e| e.position >= tmp)
+                        || tokens.last().is_some_and(|token| token.0 == UNKNOWN);
+                    if !reported {
+                        state.restore(saved.clone());
+                        let error = self.error(&self.initial_rule, None, (tmp, end), &[], &state);
+                        state.errors.push(error);
+                    }
+                    tokens.push(Token(UNKNOWN, ((tmp, end), None)));
+                    tmp = end;
+                }
             }
 
-            tokens.push(token);
-            position = tmp + 1;
+            state.restore(saved);
+            position = tmp;
+        }
+
+        let mut stats = MemoStats {
+            hits: state.hits,
+            ..Default::default()
+        };
+        for memo in state.memo.iter().flat_map(|memo| memo.values()) {
+            stats.entries += 1;
+            stats.bytes += std::mem::size_of::<((RuleId, usize, bool), Memo)>()
+                + memo.errors.len() * std::mem::size_of::<Error>()
+                + memo.failures.1.len() * std::mem::size_of::<&str>();
         }
 
-        Parsed::new(input, Token(ROOT, ((0, input.len()), Some(tokens))), errors)
+        let parsed = Parsed::new(
+            input,
+            Token(ROOT, ((0, input.len()), Some(Arc::new(tokens)))),
+            state.errors,
+        );
+        (parsed, stats)
     }
 
-    fn resursive_parse(
-        &self,
+    // devuelve el token de la regla y si la regla se cumplio
+    // nota: con memoizacion cada regla se analiza una sola vez en cada posicion
+    fn resursive_parse<'a>(
+        &'a self,
+        rule: RuleId,
+        start: &mut usize,
+        state: &mut State<'a>,
+    ) -> (Token<'a>, bool) {
+        let first = *start;
+        let key = (rule, first, state.atomic);
+        // las reglas recursivas por la izquierda no se memoizan, su resultado depende del
+        // resultado parcial del ciclo
+        let left_recursive = self.table.rules[rule].left_recursive;
+
+        // si la regla ya se analizo en esta posicion se repiten sus efectos
+        // nota: dentro de un ciclo se repite el resultado parcial de la regla que crece
+        let memo = if left_recursive {
+            state.seeds.get(&key)
+        } else {
+            state.memo.as_ref().and_then(|memo| memo.get(&key))
+        };
+        if let Some(memo) = memo {
+            let (token, matched, end) = (memo.token.clone(), memo.matched, memo.end);
+            let (errors, failures, label) =
+                (memo.errors.clone(), memo.failures.clone(), memo.label);
+            state.errors.extend(errors);
+            state.restore(failures);
+            state.label = label;
+            state.hits += usize::from(!left_recursive);
+            *start = end;
+            return (token, matched);
+        }
+
+        let errors = state.errors.len();
+        // los fallos de esta regla se registran aparte para reportarlos en sus errores
+        let saved = state.take(*start);
+        let (token, matched) = if self.table.rules[rule].grows {
+            self.grow(rule, key, start, state)
+        } else {
+            self.parse_rule(rule, start, state)
+        };
+
+        let failures = if state.memo.is_some() {
+            (state.furthest, state.expected.clone())
+        } else {
+            (0, Arc::default())
+        };
+        state.restore(saved);
+
+        if let Some(memo) = state.memo.as_mut().filter(|_| !left_recursive) {
+            let errors = state.errors[errors..].to_vec();
+            let entry = Memo {
+                token: token.clone(),
+                matched,
+                end: *start,
+                errors,
+                failures,
+                label: state.label,
+            };
+            memo.insert(key, entry);
+        }
+
+        (token, matched)
+    }
+
+    // analiza una regla recursiva por la izquierda haciendo crecer su resultado: la llamada
+    // recursiva en la misma posicion primero falla, y la regla se vuelve a analizar con el
+    // resultado anterior mientras consiga avanzar mas
+    fn grow<'a>(
+        &'a self,
+        rule: RuleId,
+        key: (RuleId, usize, bool),
+        start: &mut usize,
+        state: &mut State<'a>,
+    ) -> (Token<'a>, bool) {
+        let first = *start;
+        let errors = state.errors.len();
+        let seed = Memo {
+            token: Token(
+                &self.table.rules[rule].name,
+                ((first, first), Some(Arc::default())),
+            ),
+            matched: false,
+            end: first,
+            errors: Vec::new(),
+            failures: (0, Arc::default()),
+            label: None,
+        };
+        state.seeds.insert(key, seed);
+
+        loop {
+            let mut end = first;
+            let (token, matched) = self.parse_rule(rule, &mut end, state);
+
+            // se conserva el resultado anterior si el nuevo no avanza mas
+            let seed = &state.seeds[&key];
+            if !matched || (seed.matched && end <= seed.end) {
+                state.errors.truncate(errors);
+                break;
+            }
+
+            let seed = Memo {
+                token,
+                matched,
+                end,
+                errors: state.errors.split_off(errors),
+                failures: (0, Arc::default()),
+                label: None,
+            };
+            state.seeds.insert(key, seed);
+        }
+
+        let seed = state.seeds.remove(&key).unwrap();
+        state.errors.extend(seed.errors);
+        *start = seed.end;
+        (seed.token, seed.matched)
+    }
+
+    // analiza la regla y, si se recupera de errores y tuvo alguno, conserva solo el
+    // primero y salta hasta el siguiente punto de sincronizacion
+    fn parse_rule<'a>(
+        &'a self,
+        rule: RuleId,
+        start: &mut usize,
+        state: &mut State<'a>,
+    ) -> (Token<'a>, bool) {
+        let first = *start;
+        let errors = state.errors.len();
+        let (mut token, matched) = self.parse_options(rule, start, state);
+
+        // si la regla falla sin avanzar se reporta por su nombre y no por lo que contiene
+        if !matched && state.furthest == first {
+            let description = self.table.rules[rule].description.as_str();
+            state.expected = Arc::new(BTreeSet::from([description]));
+        }
+
+        if !matched || state.errors.len() == errors || !self.table.rules[rule].recover {
+            return (token, matched);
+        }
+
+        state.errors.truncate(errors + 1);
+
+        // nota: los espacios y comentarios que la regla salto al final no cuentan, como los
+        // de antes de un `else` vacio despues de `}`
+        let text = &state.input[first..last_content(&token).max(first)];
+        if !self.recovery.is_synchronized(text) {
+            let end = self
+                .recovery
+                .skip(state.input, *start, self.recovery.depth(text));
+
+            if end > *start {
+                if let Some(children) = &mut token.1 .1 {
+                    Arc::make_mut(children).push(Token(ERROR, ((*start, end), None)));
+                }
+                token.1 .0 .1 = end;
+                *start = end;
+            }
+        }
+
+        (token, matched)
+    }
+
+    fn parse_options<'a>(
+        &'a self,
+        rule: RuleId,
+        start: &mut usize,
+        state: &mut State<'a>,
+    ) -> (Token<'a>, bool) {
+        let rule = &self.table.rules[rule];
+        let first = *start;
+        // las reglas que usa una regla atomica tambien son atomicas
+        let atomic = state.atomic;
+        state.atomic |= rule.atomic;
+        let (tokens, matched) = self.parse_alternatives(&rule.name, &rule.options, start, state);
+        state.atomic = atomic;
+
+        // nota: las hojas de las reglas atomicas cubren el mismo texto que sus tokens
+        let children = (!rule.leaf).then(|| Arc::new(tokens));
+        (Token(&rule.name, ((first, *start), children)), matched)
+    }
+
+    // agrega el token de una regla, o sus hijos si la regla es silenciosa
+    fn push_token<'a>(&'a self, rule: RuleId, token: Token<'a>, tokens: &mut Vec<Token<'a>>) {
+        match token.1 .1 {
+            Some(children) if self.table.rules[rule].silent => {
+                tokens.extend(children.iter().cloned())
+            }
+            _ => tokens.push(token),
+        }
+    }
+
+    // prueba las opciones de una regla o de un grupo y devuelve los tokens de la que se
+    // cumple y si alguna se cumplio
+    // nota: los errores se reportan en la regla que contiene las opciones
+    fn parse_alternatives<'a>(
+        &'a self,
         rule: &'a str,
+        options: &'a [Sequence],
         start: &mut usize,
-        errors: &mut Vec<Error<'a>>,
-        input: &'a str,
-    ) -> Token<'a> {
+        state: &mut State<'a>,
+    ) -> (Vec<Token<'a>>, bool) {
         let mut tokens = Vec::new();
         // se almacenan los candidatos por si alguna regla no se cumple
         // y tomar al que tenga mayor score tenga
         let mut candidates = Vec::new();
+        // mensaje de la primera opcion que fallo sin avanzar en su ultima expresion
+        let mut label = None;
 
-        if let Some(options) = self.grammar.get(rule) {
-            // se itera sobre las opciones de la regla
-            'options: for option in options {
-                let mut score = 0;
-                let mut local_start = *start;
-                let mut temp_tokens = Vec::new();
-
-                // se itera sobre las expresiones de la opcion
-                // Nota: las expresiones pueden ser strings, reglas, reglas internas o keywords
-                for expression in option {
-                    match expression {
-                        Expression::String(string) => {
-                            if input[local_start..].starts_with(string) {
-                                // se avanza la posicion y se aumenta el score
-                                local_start += string.len();
-                                score += 1;
-
-                                temp_tokens.push(Token(
-                                    "string",
-                                    ((local_start - string.len(), local_start), None),
-                                ));
-                            } else {
-                                // si no se cumple la regla se almacena como candidato
-                                candidates.push((
-                                    local_start,
-                                    score as f32 / option.len() as f32,
-                                    temp_tokens,
-                                ));
-                                continue 'options;
-                            }
-                        }
-                        Expression::Rule(r) => {
-                            // se llama recursivamente a la regla y se almacena el resultado
-                            let temp = self.resursive_parse(r, &mut local_start, errors, input);
+        // se itera sobre las opciones de la regla
+        'options: for option in options {
+            let mut score = 0;
+            let mut local_start = *start;
+            let mut temp_tokens = Vec::new();
+
+            // se itera sobre las expresiones de la opcion
+            // Nota: las expresiones pueden ser strings, reglas, reglas internas, keywords o
+            // repeticiones de ellas
+            for (i, item) in option.items.iter().enumerate() {
+                if i > 0 {
+                    self.skip_trivia(&mut local_start, &mut temp_tokens, state);
+                }
 
-                            // si no se cumple la regla va a la siguiente opcion
-                            if local_start == *start {
-                                continue 'options;
-                            }
+                state.label = None;
+                let length = temp_tokens.len();
+                let matched =
+                    self.parse_item(rule, item, &mut local_start, &mut temp_tokens, state);
 
-                            score += 1;
-                            temp_tokens.push(temp);
-                        }
-                        Expression::InternalRule(rule) => {
-                            // se salta las reglas internas vacias
-                            if rule == &EMPTY {
-                                continue;
-                            }
-
-                            let mut end = local_start;
-
-                            while end < input.len() {
-                                if INTERNAL_RULE[rule](&input[end..end + 1]) {
-                                    end += 1;
-                                } else {
-                                    break;
-                                }
-                            }
-
-                            if local_start == end {
-                                candidates.push((
-                                    local_start,
-                                    score as f32 / option.len() as f32,
-                                    temp_tokens,
-                                ));
-                                continue 'options;
-                            }
-
-                            temp_tokens.push(Token("internal_rule", ((local_start, end), None)));
-                            local_start = end;
-                            score += 1;
+                // nota: el mensaje de la opcion solo describe el fallo de su ultima expresion
+                let last = i + 1 == option.items.len();
+                if !matched && last && local_start == *start && label.is_none() {
+                    label = option.label.as_deref().map(|label| (local_start, label));
+                }
+
+                match item.base() {
+                    // se salta las reglas internas vacias
+                    Item::Empty => {}
+                    // si la regla no avanza va a la siguiente opcion
+                    // nota: los grupos y las expresiones con operadores se tratan como reglas
+                    // escritas dentro de la opcion
+                    Item::Rule(_) | Item::Group(_) | Item::Precedence(_)
+                        if local_start == *start
+                            && (!matched || !matches!(item, Item::Repeat { .. })) =>
+                    {
+                        continue 'options;
+                    }
+                    _ if matched => score += 1,
+                    Item::Keyword { .. } | Item::Predicate { .. } => continue 'options,
+                    _ => {
+                        // la regla que falla no queda en el arbol, no consumio texto
+                        if matches!(item.base(), Item::Rule(_)) {
+                            temp_tokens.truncate(length);
                         }
-                        Expression::Keyword(rule) => {
-                            if let Expression::String(string) = &self.grammar[rule][0][0] {
-                                if input[local_start..].starts_with(string) {
-                                    temp_tokens.push(Token(
-                                        "keyword",
-                                        ((local_start, local_start + string.len()), None),
-                                    ));
-                                    local_start += string.len();
-                                    score += 1;
-                                } else {
-                                    continue 'options;
-                                }
-                            }
+
+                        // una regla o un grupo con un mensaje para el fallo mas lejano se
+                        // reporta con ese mensaje
+                        let label = state.label.take().filter(|(position, _)| {
+                            *position == local_start && *position == state.furthest
+                        });
+                        if let Some((_, label)) = label {
+                            let range = (*start, local_start);
+                            let error = self.error(rule, Some(label), range, &temp_tokens, state);
+                            state.errors.push(error);
                         }
+
+                        // si no se cumple la expresion se almacena como candidato
+                        // nota: si el fallo ya se reporto no se vuelve a reportar
+                        candidates.push((
+                            local_start,
+                            score as f32 / option.items.len() as f32,
+                            temp_tokens,
+                            option.label.as_deref().filter(|_| last),
+                            label.is_some(),
+                        ));
+                        continue 'options;
                     }
                 }
-
-                // se da la posicion del token y se almacena
-                let position = (*start, local_start);
-                *start = local_start;
-                tokens.append(&mut temp_tokens);
-                return Token(rule, ((position.0, position.1), Some(tokens)));
             }
+
+            // se da la posicion del token y se almacena
+            *start = local_start;
+            tokens.append(&mut temp_tokens);
+            return (tokens, true);
         }
 
         // si ninguna regla se cumple se toma el candidato con mayor score
-        if let Some((local_start, _, temp_tokens)) = candidates
+        if let Some((local_start, _, temp_tokens, label, reported)) = candidates
             .iter_mut()
             .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
         {
             // se sabe que no es una regla interna, ni un string, omite desviaciones y
             // escala hasta tener mayor informacion del error
-            if temp_tokens.len() > 1 {
-                errors.push(Error {
-                    message: "Syntax error",
-                    first: *start,
-                    last: *local_start,
-                });
-
-                let position = (*start, *local_start);
+            // nota: los espacios y comentarios no cuentan, `x <` no es una llamada a `x`
+            let trivia = |token: &&Token| [WHITESPACE, COMMENT].contains(&token.0);
+            if temp_tokens.iter().filter(|token| !trivia(token)).count() > 1 {
+                if !*reported {
+                    let range = (*start, *local_start);
+                    let error = self.error(rule, *label, range, temp_tokens, state);
+                    state.errors.push(error);
+                }
+
                 *start = *local_start;
                 tokens.append(temp_tokens);
-                return Token(rule, (position, Some(tokens)));
+                return (tokens, true);
             }
         }
 
         // si no hay candidatos se asume que hay un error de sintaxis
-        let position = (*start, *start);
-        Token(rule, (position, Some(tokens)))
+        // nota: quien contiene la regla o el grupo decide si reporta el mensaje
+        state.label = label;
+        (tokens, false)
+    }
+
+    // analiza una expresion de una opcion, agrega sus tokens y devuelve si se cumplio
+    // nota: si no se cumple la posicion no avanza, salvo en las reglas que se recuperan
+    fn parse_item<'a>(
+        &'a self,
+        rule: &'a str,
+        item: &'a Item,
+        position: &mut usize,
+        tokens: &mut Vec<Token<'a>>,
+        state: &mut State<'a>,
+    ) -> bool {
+        let input = state.input;
+
+        match item {
+            Item::String { text, expected } | Item::Keyword { text, expected, .. } => {
+                // las keywords que terminan en una letra deben terminar la palabra: `if` no
+                // se reconoce al inicio de `iffy`
+                let word = matches!(item, Item::Keyword { word: true, .. });
+                let end = *position + text.len();
+
+                if !input.as_bytes()[*position..].starts_with(text.as_bytes())
+                    || (word
+                        && input[end..]
+                            .chars()
+                            .next()
+                            .is_some_and(unicode_ident::is_xid_continue))
+                {
+                    state.fail(*position, expected);
+                    return false;
+                }
+
+                let name = match item {
+                    Item::Keyword { .. } => "keyword",
+                    _ => "string",
+                };
+                tokens.push(Token(name, ((*position, end), None)));
+                *position = end;
+                true
+            }
+            Item::Rule(rule) => {
+                // se llama recursivamente a la regla y se almacena el resultado
+                let (token, matched) = self.resursive_parse(*rule, position, state);
+                self.push_token(*rule, token, tokens);
+                matched
+            }
+            Item::Class { class, expected } => {
+                // se avanza mientras los caracteres cumplan la regla
+                let end = *position + class.span(&input[*position..]);
+
+                if *position == end {
+                    if let Some(expected) = expected {
+                        state.fail(*position, expected);
+                    }
+                    return false;
+                }
+
+                tokens.push(Token("internal_rule", ((*position, end), None)));
+                *position = end;
+                true
+            }
+            Item::Set { set, expected } => match input[*position..].chars().next() {
+                Some(c) if set.contains(c) => {
+                    let end = *position + c.len_utf8();
+                    tokens.push(Token("char", ((*position, end), None)));
+                    *position = end;
+                    true
+                }
+                _ => {
+                    state.fail(*position, expected);
+                    false
+                }
+            },
+            Item::Predicate { item, negated } => {
+                // se analiza la expresion y se descartan sus tokens y sus errores
+                let (length, errors, mut end) = (tokens.len(), state.errors.len(), *position);
+                let saved = state.take(*position);
+                let matched = self.parse_item(rule, item, &mut end, tokens, state);
+                tokens.truncate(length);
+                state.errors.truncate(errors);
+
+                // lo que se esperaba dentro del predicado solo se reporta si `&a` falla
+                if *negated || matched {
+                    state.take(*position);
+                }
+                state.restore(saved);
+
+                matched != *negated
+            }
+            Item::Empty => true,
+            Item::Repeat { item, min, max } => {
+                // se repite sin recursion y los tokens de cada vuelta quedan como hermanos
+                let (mut count, mut end, initial) = (0, *position, tokens.len());
+
+                while max.is_none_or(|max| count < max) {
+                    let (length, mut next) = (tokens.len(), end);
+                    if count > 0 {
+                        self.skip_trivia(&mut next, tokens, state);
+                    }
+
+                    // se detiene si la expresion no se cumple o no avanza
+                    if !self.parse_item(rule, item, &mut next, tokens, state) || next == end {
+                        tokens.truncate(length);
+                        break;
+                    }
+
+                    end = next;
+                    count += 1;
+                }
+
+                if count < *min {
+                    tokens.truncate(initial);
+                    return false;
+                }
+
+                *position = end;
+                true
+            }
+            Item::Group(options) => {
+                // las opciones se prueban igual que las de una regla y los tokens quedan como
+                // hijos de la regla que contiene el grupo
+                let (mut children, matched) =
+                    self.parse_alternatives(rule, options, position, state);
+                tokens.append(&mut children);
+                matched
+            }
+            Item::Precedence(precedence) => {
+                // la operacion que une menos queda directamente en la regla
+                match self.parse_operation(rule, precedence, 0, position, state) {
+                    Some(operation) => {
+                        tokens.extend(operation.tokens);
+                        true
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+
+    // analiza una expresion con operadores por precedencia (precedence climbing): despues de
+    // un operando se busca un operador de un nivel desde `min`, y el operando derecho solo
+    // puede contener operadores de los niveles que unen mas (o del mismo si asocia por la
+    // derecha)
+    fn parse_operation<'a>(
+        &'a self,
+        rule: &'a str,
+        precedence: &'a Precedence,
+        min: usize,
+        position: &mut usize,
+        state: &mut State<'a>,
+    ) -> Option<Operation<'a>> {
+        let mut left = self.parse_unary(rule, precedence, position, state)?;
+
+        loop {
+            let (mut end, mut tokens) = (*position, Vec::new());
+            self.skip_trivia(&mut end, &mut tokens, state);
+
+            let mut level = None;
+            for (i, candidate) in precedence.levels.iter().enumerate().skip(min) {
+                let (mut operator, matched) =
+                    self.parse_alternatives(rule, &candidate.operators, &mut end, state);
+                if matched {
+                    tokens.append(&mut operator);
+                    level = Some(if candidate.right { i } else { i + 1 });
+                    break;
+                }
+            }
+
+            let Some(level) = level else { break };
+            self.skip_trivia(&mut end, &mut tokens, state);
+
+            // el operador sin operando derecho no forma parte de la operacion: la operacion
+            // termina antes de el y quien la contiene reporta el operando que falta
+            let Some(right) = self.parse_operation(rule, precedence, level, &mut end, state) else {
+                break;
+            };
+
+            let first = left.first;
+            let mut children = left.into_tokens(rule);
+            children.append(&mut tokens);
+            children.extend(right.into_tokens(rule));
+            left = Operation::new(children, first, end);
+            *position = end;
+        }
+
+        Some(left)
+    }
+
+    // analiza un operando con sus operadores prefijos y posfijos
+    fn parse_unary<'a>(
+        &'a self,
+        rule: &'a str,
+        precedence: &'a Precedence,
+        position: &mut usize,
+        state: &mut State<'a>,
+    ) -> Option<Operation<'a>> {
+        let first = *position;
+        let mut end = first;
+
+        let (mut tokens, matched) =
+            self.parse_alternatives(rule, &precedence.prefix, &mut end, state);
+        let mut operation = if matched {
+            // el operando de un prefijo puede tener otros prefijos: `- -a` es `-(-a)`
+            self.skip_trivia(&mut end, &mut tokens, state);
+            let operand = self.parse_unary(rule, precedence, &mut end, state)?;
+            tokens.extend(operand.into_tokens(rule));
+            Operation::new(tokens, first, end)
+        } else {
+            let mut tokens = Vec::new();
+            if !self.parse_item(rule, &precedence.operand, &mut end, &mut tokens, state) {
+                return None;
+            }
+            Operation {
+                tokens,
+                first,
+                last: end,
+                nested: false,
+            }
+        };
+
+        // los posfijos unen mas que los prefijos: `-a!` es `-(a!)`
+        while !precedence.postfix.is_empty() {
+            let (mut next, mut tokens) = (end, Vec::new());
+            self.skip_trivia(&mut next, &mut tokens, state);
+
+            let (mut operator, matched) =
+                self.parse_alternatives(rule, &precedence.postfix, &mut next, state);
+            if !matched {
+                break;
+            }
+
+            let mut children = operation.into_tokens(rule);
+            children.append(&mut tokens);
+            children.append(&mut operator);
+            operation = Operation::new(children, first, next);
+            end = next;
+        }
+
+        *position = end;
+        Some(operation)
+    }
+
+    // salta los espacios y comentarios que define la gramatica y agrega sus tokens
+    // nota: solo se aceptan si se cumplen sin errores, y lo que no se encontro en ellos no
+    // se reporta como esperado
+    fn skip_trivia<'a>(
+        &'a self,
+        position: &mut usize,
+        tokens: &mut Vec<Token<'a>>,
+        state: &mut State<'a>,
+    ) {
+        if state.atomic || self.table.trivia.is_empty() {
+            return;
+        }
+
+        let saved = state.take(*position);
+        let errors = state.errors.len();
+
+        'trivia: loop {
+            for &rule in &self.table.trivia {
+                let mut end = *position;
+                let (token, matched) = self.resursive_parse(rule, &mut end, state);
+
+                if matched && end > *position && state.errors.len() == errors {
+                    self.push_token(rule, token, tokens);
+                    *position = end;
+                    continue 'trivia;
+                }
+                state.errors.truncate(errors);
+            }
+            break;
+        }
+
+        state.take(*position);
+        state.restore(saved);
+    }
+
+    // construye el error de una regla con lo que se esperaba en el punto mas lejano
+    // nota: si la opcion que fallo tiene un mensaje en la gramatica se usa ese mensaje
+    fn error(
+        &self,
+        rule: &str,
+        label: Option<&str>,
+        (first, last): (usize, usize),
+        tokens: &[Token],
+        state: &State,
+    ) -> Error {
+        let expected: Vec<String> = state.expected.iter().map(|e| e.to_string()).collect();
+        let found = state.found(state.furthest);
+
+        // lo ultimo que se reconocio antes del error, sin contar los espacios
+        let after = tokens
+            .iter()
+            .rev()
+            .map(|token| (token.0, &state.input[token.1 .0 .0..token.1 .0 .1]))
+            .find(|(_, value)| !value.trim().is_empty())
+            .map(|(rule, value)| match rule {
+                "string" | "keyword" => format!("`{}`", value),
+                _ => describe_rule(rule),
+            });
+
+        let message = match (label, after) {
+            (Some(label), _) => label.to_string(),
+            (None, Some(after)) => format!(
+                "expected {} after {}, found {}",
+                join_expected(&expected),
+                after,
+                found
+            ),
+            (None, None) => format!(
+                "expected {} in {}, found {}",
+                join_expected(&expected),
+                describe_rule(rule),
+                found
+            ),
+        };
+
+        Error {
+            message,
+            rule: rule.to_string(),
+            expected,
+            found,
+            position: state.furthest,
+            first,
+            last,
+        }
     }
 }
@@ -0,0 +1,124 @@
+use crate::{analyzer::Analyzer, generate::render_page, html::check_well_formed, source::Source};
+use std::panic::{self, AssertUnwindSafe};
+
+// Plantilla mínima para comprobar el HTML generado sin depender de la plantilla completa.
+const CHECK_TEMPLATE: &str =
+    "<pre><code class=\"code\"><!-- Insert point for editor content --></code></pre>\
+<p class=\"terminal-text\"><!-- Insert errors --></p>";
+
+// Fragmentos con los que se construyen las entradas aleatorias: incluyen código válido
+// y caracteres que rompen el HTML si no se escapan.
+const FRAGMENTS: [&str; 37] = [
+    "var",
+    "if",
+    "else",
+    "while",
+    "function",
+    "return",
+    "a",
+    "b1",
+    "10",
+    "1.5",
+    " ",
+    " ",
+    "\n",
+    "\t",
+    "\r\n",
+    "=",
+    "==",
+    "!=",
+    "<",
+    ">",
+    "+",
+    "-",
+    "*",
+    "/",
+    ";",
+    ",",
+    "(",
+    ")",
+    "{",
+    "}",
+    "&",
+    "\"'<script>&amp;",
+    "canción",
+    "数量",
+    "€",
+    "👍",
+    "\u{feff}",
+];
+
+// Comprueba que la página generada para la entrada sea HTML bien formado.
+// nota: un pánico del analizador también se reporta como error
+pub fn check_html(analyzer: &Analyzer, input: &str) -> Result<(), String> {
+    let page = panic::catch_unwind(AssertUnwindSafe(|| {
+        render_page(analyzer, input, CHECK_TEMPLATE)
+    }))
+    .map_err(|_| "The analyzer panicked".to_string())?
+    .map_err(|e| e.to_string())?;
+
+    check_well_formed(&page)
+}
+
+// Genera una entrada arbitraria de forma reproducible a partir de una semilla.
+pub fn random_input(seed: u64, fragments: usize) -> String {
+    // xorshift: suficiente para variar las entradas sin agregar dependencias
+    let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    let mut input = String::new();
+
+    for _ in 0..fragments {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        input.push_str(FRAGMENTS[(state % FRAGMENTS.len() as u64) as usize]);
+    }
+
+    input
+}
+
+// Comprueba que las hojas del árbol cubran la entrada sin huecos ni solapamientos
+// y que al concatenarlas se obtenga exactamente el texto analizado y, a través del
+// mapa de posiciones, el texto original.
+pub fn check_round_trip(analyzer: &Analyzer, original: &str) -> Result<(), String> {
+    let source = Source::new(original);
+    let input = &source.text;
+    let parsed = panic::catch_unwind(AssertUnwindSafe(|| analyzer.parse(input)))
+        .map_err(|_| "The analyzer panicked".to_string())?;
+
+    let mut position = 0;
+    for leaf in parsed.leaves() {
+        let (first, last) = *leaf.position();
+        if first != position {
+            return Err(format!(
+                "Leaf '{}' starts at {}, expected {}",
+                leaf.rule(),
+                first,
+                position
+            ));
+        }
+        position = last;
+    }
+
+    if position != input.len() {
+        return Err(format!(
+            "Leaves end at {}, input ends at {}",
+            position,
+            input.len()
+        ));
+    }
+
+    if &parsed.text() != input {
+        return Err("Leaves do not reproduce the input".to_string());
+    }
+
+    let leaves = parsed.leaves();
+    let text: String = leaves
+        .iter()
+        .map(|leaf| source.original_slice(*leaf.position()))
+        .collect();
+    if text != original {
+        return Err("Leaves do not reproduce the original input".to_string());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+// Problemas encontrados al cargar y revisar una gramática: en lugar de detenerse en el
+// primero se juntan todos, cada uno con su posición en el archivo, y se escriben en un
+// informe como los de los compiladores.
+use crate::line_index::LineIndex;
+
+// rango en bytes del texto de la gramática
+pub type Span = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    // la gramática no se puede usar
+    Error,
+    // la gramática se puede usar, pero probablemente no hace lo que se espera
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    // nota: no tienen posición los problemas que no están en una línea, como una regla
+    // inicial que no existe
+    pub span: Option<Span>,
+    // otro lugar de la gramática relacionado con el problema, como la primera definición
+    // de una regla repetida
+    pub note: Option<(String, Span)>,
+}
+
+impl Diagnostic {
+    pub fn error(message: String, span: Option<Span>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message,
+            span,
+            note: None,
+        }
+    }
+
+    pub fn warning(message: String, span: Option<Span>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message,
+            span,
+            note: None,
+        }
+    }
+
+    pub fn with_note(mut self, message: String, span: Span) -> Self {
+        self.note = Some((message, span));
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+// informe de una o varias gramáticas
+#[derive(Debug, Default)]
+pub struct Report {
+    pub text: String,
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+impl Report {
+    // agrega los problemas de una gramática, con el nombre de su archivo y su texto
+    pub fn add(&mut self, diagnostics: &[Diagnostic], name: &str, text: &str) {
+        self.text.push_str(&render(diagnostics, name, text));
+        self.errors += diagnostics.iter().filter(|d| d.is_error()).count();
+        self.warnings += diagnostics.iter().filter(|d| !d.is_error()).count();
+    }
+}
+
+// escribe los problemas en orden de aparición, con la línea a la que se refiere cada uno
+// y el rango marcado debajo, seguidos de su nota si tienen una:
+//
+//   error: Invalid rule: foo
+//    --> grammar.txt:3:15
+//     |
+//   3 | instruction = foo | bar
+//     |               ^^^
+fn render(diagnostics: &[Diagnostic], name: &str, text: &str) -> String {
+    let lines = LineIndex::new(text);
+
+    let mut diagnostics: Vec<&Diagnostic> = diagnostics.iter().collect();
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.span, diagnostic.severity));
+
+    let mut report = String::new();
+    for diagnostic in diagnostics {
+        let severity = match diagnostic.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        report.push_str(&format!("{}: {}\n", severity, diagnostic.message));
+
+        let Some(span) = diagnostic.span else {
+            report.push_str(&format!(" --> {}\n\n", name));
+            continue;
+        };
+        report.push_str(&snippet(&lines, name, text, span));
+
+        if let Some((message, span)) = &diagnostic.note {
+            report.push_str(&format!("note: {}\n", message));
+            report.push_str(&snippet(&lines, name, text, *span));
+        }
+    }
+
+    report
+}
+
+// la línea del rango con el rango marcado debajo
+fn snippet(lines: &LineIndex, name: &str, text: &str, (start, end): Span) -> String {
+    let mut report = String::new();
+    let position = lines.line_col(start);
+    let line = text[start + 1 - position.column..]
+        .lines()
+        .next()
+        .unwrap_or_default();
+    let number = position.line.to_string();
+    let margin = " ".repeat(number.len());
+
+    // el rango se marca solo hasta el final de la línea, y al menos con un caracter
+    let before = &line[..position.column - 1];
+    let marked = line[before.len()..]
+        .get(..end.saturating_sub(start))
+        .unwrap_or(&line[before.len()..]);
+    let padding: String = before
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    report.push_str(&format!(
+        "{}--> {}:{}:{}\n",
+        margin, name, position.line, position.column_utf16
+    ));
+    report.push_str(&format!("{} |\n", margin));
+    report.push_str(&format!("{} | {}\n", number, line));
+    report.push_str(&format!(
+        "{} | {}{}\n\n",
+        margin,
+        padding,
+        "^".repeat(marked.chars().count().max(1))
+    ));
+
+    report
+}
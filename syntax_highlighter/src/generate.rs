@@ -1,150 +1,248 @@
 use crate::{
     analyzer::{Analyzer, Error},
-    utils::preprocess_content,
+    html::{escape, push_span},
+    line_index::LineIndex,
+    parsed::{Chunk, Parsed},
+    source::Source,
+};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
 };
-use std::fs::{self, DirEntry};
 
-// Función auxiliar para calcular el número de línea en el archivo de entrada a partir de una posición dada.
-fn calculate_line_number_from_position(position: usize, input: &str) -> usize {
-    input[..position].matches('\n').count() + 2
+// Estilo de las líneas del editor que contienen un error.
+const ERROR_LINE_STYLE: &str = "background-color: rgba(255, 0, 0, 0.3);";
+
+// Acumula el HTML del editor: agrupa el texto contiguo que tiene la misma clase y
+// abre una línea nueva por cada salto de línea, de forma que no se pierda ningún carácter.
+// Las líneas donde empieza un error se resaltan al abrirlas.
+struct Editor<'a> {
+    html: String,
+    line: usize,
+    error_lines: HashSet<usize>,
+    class: Option<&'a str>,
+    pending: String,
+    carriage_return: bool,
 }
 
-// Función para resaltar errores en el contenido HTML basado en las posiciones de los errores parseados.
-pub fn highlight_errors_in_html(errors: &[Error], html_content: &mut String, input: &str) {
-    let code_start_marker = "<code class=\"code\">";
-    let code_end_marker = "</code>";
-
-    if let Some(code_block_start) = html_content.find(code_start_marker) {
-        if let Some(code_block_end) = html_content.find(code_end_marker) {
-            let code_block_content =
-                &html_content[code_block_start + code_start_marker.len()..code_block_end];
-            let code_lines: Vec<&str> = code_block_content.split("<br>").collect();
-            let mut new_code_block_content = String::new();
-
-            for (i, line) in code_lines.iter().enumerate() {
-                let line_num = i + 1;
-                let is_error_line = errors.iter().any(|error| {
-                    let start_line = calculate_line_number_from_position(error.first, input);
-                    line_num == start_line
-                });
-
-                if is_error_line {
-                    new_code_block_content.push_str(&format!(
-                        "<span style=\"background-color: rgba(255, 0, 0, 0.3);\">{}</span><br>",
-                        line
-                    ));
-                } else {
-                    new_code_block_content.push_str(line);
-                    if i < code_lines.len() - 1 {
-                        new_code_block_content.push_str("<br>");
-                    }
-                }
-            }
+impl<'a> Editor<'a> {
+    fn new(error_lines: HashSet<usize>) -> Self {
+        let mut editor = Editor {
+            html: String::from("<span></span><br>"),
+            line: 1,
+            error_lines,
+            class: None,
+            pending: String::new(),
+            carriage_return: false,
+        };
+        editor.open_line();
+        editor
+    }
 
-            let new_code_content = format!(
-                "{}{}{}",
-                code_start_marker, new_code_block_content, code_end_marker
-            );
-            html_content.replace_range(
-                code_block_start..code_block_end + code_end_marker.len(),
-                &new_code_content,
-            );
+    fn open_line(&mut self) {
+        if self.error_lines.contains(&self.line) {
+            self.html.push_str(&format!(
+                "<span class=\"line\" style=\"{}\">{} ",
+                ERROR_LINE_STYLE, self.line
+            ));
+        } else {
+            self.html
+                .push_str(&format!("<span class=\"line\">{} ", self.line));
         }
     }
-}
 
-pub fn create_mark(analyzer: &Analyzer, entry: &DirEntry, template: &String) {
-    let input_path = entry.path();
-    let input_filename = input_path.file_stem().unwrap().to_str().unwrap().to_string();
-    let input = fs::read_to_string(&input_path).expect("Failed to read file");
-
-    // Preprocesa el contenido para asegurarte de que todos los caracteres sean ASCII.
-    let preprocessed_input = preprocess_content(&input);
-
-    // Procesa el contenido del archivo de texto.
-    let parsed = analyzer.parse(&preprocessed_input);
-
-    let mut html_content = template.clone();
-    let mut generated_content = format!("<span></span><br>1 ");
-    let mut count = 1;
-
-    parsed.visit(&mut |chunk| match chunk.rule() {
-        "WHITESPACE*" | "WHITESPACE+" => {
-            let content = chunk.value();
-            for c in content.chars() {
-                match c {
-                    '\n' => {
-                        generated_content.push_str("</span>");
-                        count += 1;
-                        generated_content.push_str(&format!("<br><span class=\"line\">{} ", count));
+    fn push(&mut self, class: Option<&'a str>, text: &str) {
+        for c in text.chars() {
+            // el texto es el original: "\r\n" y "\r" también son saltos de línea
+            let after_carriage_return = self.carriage_return;
+            self.carriage_return = c == '\r';
+
+            match c {
+                '\n' if after_carriage_return => {}
+                '\n' | '\r' => self.new_line(),
+                _ => {
+                    if class != self.class {
+                        self.flush();
+                        self.class = class;
                     }
-                    ' ' => generated_content.push_str("&nbsp;"),
-                    _ => {}
+                    self.pending.push(c);
                 }
             }
         }
+    }
+
+    fn new_line(&mut self) {
+        self.flush();
+        self.html.push_str("</span><br>");
+        self.line += 1;
+        self.open_line();
+    }
+
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        match self.class {
+            Some(class) => push_span(&mut self.html, class, &self.pending),
+            None => self
+                .html
+                .push_str(&escape(&self.pending).replace(' ', "&nbsp;")),
+        }
+        self.pending.clear();
+    }
+
+    fn finish(mut self) -> String {
+        self.flush();
+        // se cierra la última línea
+        self.html.push_str("</span>");
+        self.html
+    }
+}
+
+// Clase con la que se resalta un token; los hijos heredan la clase de su ancestro.
+fn class_of<'a>(chunk: &Chunk<'a, '_>) -> Option<&'a str> {
+    match chunk.rule() {
+        "keyword"
+        | "ident"
+        | "number"
+        | "logical_operators"
+        | "algebraic_operators"
+        | "unknown"
+        | "error" => Some(chunk.rule()),
+        // los operadores de cada nivel de precedencia se resaltan igual
+        "additive_operators" | "multiplicative_operators" => Some("algebraic_operators"),
+        // los comentarios que la gramatica salta con la regla `COMMENT`
+        "COMMENT" => Some("comment"),
         "string" => match chunk.value() {
-            "," | "." | ";" | "=" => {
-                let span = format!("<span class=\"white\">{}</span>", chunk.value());
-                generated_content.push_str(&span);
-            }
-            "{" | "(" | ")" | "}" => {
-                let span = format!("<span class=\"brackets\">{}</span>", chunk.value());
-                generated_content.push_str(&span);
-            }
-            _ => {}
+            "," | "." | ";" | "=" => Some("white"),
+            "{" | "(" | ")" | "}" => Some("brackets"),
+            _ => None,
         },
-        "unknown" => {
-            let span = format!("<span class=\"unknown\">{}</span>", chunk.value());
-            generated_content.push_str(&span);
-        }
-        _ => {
-            if generated_content.ends_with("<br>") || generated_content.is_empty() {
-                generated_content.push_str(&format!("{} ", count));
-            }
-            let capture = match chunk.rule() {
-                "keyword" | "ident" | "number" | "logical_operators" | "algebraic_operators" => {
-                    Some(chunk.value())
-                }
-                _ => None,
-            };
+        _ => None,
+    }
+}
 
-            if let Some(capture) = capture {
-                let span = format!("<span class=\"{}\">{}</span>", chunk.rule(), capture);
-                generated_content.push_str(&span);
-            }
+// Recorre el árbol y escribe el texto original de cada hoja con la clase que le corresponde.
+fn render_chunk<'a>(
+    editor: &mut Editor<'a>,
+    source: &Source,
+    chunk: &Chunk<'a, '_>,
+    inherited: Option<&'a str>,
+) {
+    let class = inherited.or_else(|| class_of(chunk));
+
+    if chunk.is_leaf() {
+        editor.push(class, source.original_slice(*chunk.position()));
+    } else {
+        for child in chunk.child_chunks() {
+            render_chunk(editor, source, &child, class);
         }
-    });
+    }
+}
 
+// Genera el contenido del editor (el código resaltado) a partir del resultado del análisis.
+// nota: se escriben todas las hojas del árbol, por lo que el texto de la entrada se conserva
+pub fn highlight_parsed(source: &Source, parsed: &Parsed) -> String {
+    let lines = LineIndex::new(source.original);
+    let error_lines = parsed
+        .errors
+        .iter()
+        .map(|error| lines.line(source.original_offset(error.position)))
+        .collect();
+
+    let mut editor = Editor::new(error_lines);
+    render_chunk(&mut editor, source, &parsed.root(), None);
+    editor.finish()
+}
+
+// Analiza el texto y devuelve el código resaltado, sin depender del sistema de archivos.
+pub fn highlight_str(analyzer: &Analyzer, input: &str) -> String {
+    // Normaliza el contenido antes de analizarlo.
+    let source = Source::new(input);
+    highlight_parsed(&source, &analyzer.parse(&source.text))
+}
+
+// Genera los mensajes de error que se muestran en la terminal de la página.
+// nota: las posiciones se reportan sobre el texto original
+pub fn render_errors(source: &Source, errors: &[Error]) -> String {
+    let lines = LineIndex::new(source.original);
+
+    errors
+        .iter()
+        .map(|error| {
+            let position = lines.line_col(source.original_offset(error.position));
+            format!(
+                "&gt;&gt;Error: {} en la línea {}, columna {} \n <br>",
+                escape(&error.message),
+                position.line,
+                position.column_utf16
+            )
+        })
+        .collect::<String>()
+}
+
+// Genera la página completa: inserta el código resaltado y los errores en la plantilla.
+// nota: falla si a la plantilla le falta alguno de los marcadores de inserción
+pub fn render_page(analyzer: &Analyzer, input: &str, template: &str) -> anyhow::Result<String> {
     let content_insert = "<!-- Insert point for editor content -->";
     let error_insert = "<!-- Insert errors -->";
 
-    if let Some(insert_pos) = html_content.find(content_insert) {
-        html_content.insert_str(insert_pos, &generated_content);
-    } else {
-        println!("Marcador de inserción de contenido no encontrado.");
+    let content_pos = template
+        .find(content_insert)
+        .ok_or_else(|| anyhow::anyhow!("Template has no `{}` marker", content_insert))?;
+    let error_pos = template
+        .find(error_insert)
+        .ok_or_else(|| anyhow::anyhow!("Template has no `{}` marker", error_insert))?;
+
+    // Normaliza el contenido antes de analizarlo.
+    let source = Source::new(input);
+
+    // Procesa el contenido del texto.
+    let parsed = analyzer.parse(&source.text);
+
+    let mut html_content = template.to_string();
+
+    // Inserta primero en la posición más avanzada para no mover la del otro marcador.
+    let mut inserts = [
+        (content_pos, highlight_parsed(&source, &parsed)),
+        (
+            error_pos + error_insert.len(),
+            render_errors(&source, &parsed.errors),
+        ),
+    ];
+    inserts.sort_by_key(|(pos, _)| std::cmp::Reverse(*pos));
+    for (pos, content) in inserts {
+        html_content.insert_str(pos, &content);
     }
 
-    if !parsed.errors.is_empty() {
-        let error_content = parsed
-            .errors
-            .iter()
-            .map(|error| {
-                format!(
-                    ">>Error: {} en la posición {} a {} \n <br>",
-                    error.message, error.first, error.last
-                )
-            })
-            .collect::<String>();
-
-        if let Some(insert_pos) = html_content.find(error_insert) {
-            html_content.insert_str(insert_pos + error_insert.len(), &error_content);
-            highlight_errors_in_html(&parsed.errors, &mut html_content, &input);
-        } else {
-            println!("Marcador de inserción de errores no encontrado.");
-        }
-    }
+    Ok(html_content)
+}
+
+// Ruta del HTML de un archivo en la carpeta de salida.
+// nota: se conserva la extensión para que `foo.txt` y `foo.calc` no escriban el mismo HTML
+pub fn output_path(input_path: &Path, output: &Path) -> PathBuf {
+    let mut name = input_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".html");
+    output.join(name)
+}
 
-    let output_path = format!("results/{}.html", input_filename);
-    fs::write(output_path, html_content).expect("Failed to write HTML file");
+// Procesa un archivo y escribe su página HTML en la carpeta de salida.
+// nota: los errores incluyen la ruta del archivo, para reportarlos sin detener el resto
+pub fn create_mark(
+    analyzer: &Analyzer,
+    input_path: &Path,
+    template: &str,
+    output: &Path,
+) -> anyhow::Result<()> {
+    let input = fs::read_to_string(input_path)
+        .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", input_path.display(), e))?;
+
+    let html_content = render_page(analyzer, &input, template)
+        .map_err(|e| anyhow::anyhow!("{}: {}", input_path.display(), e))?;
+
+    let output_path = output_path(input_path, output);
+    fs::write(&output_path, html_content)
+        .map_err(|e| anyhow::anyhow!("Cannot write {}: {}", output_path.display(), e))
 }
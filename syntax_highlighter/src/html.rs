@@ -0,0 +1,128 @@
+// Capa de renderizado: todo texto que proviene de la entrada (o de los mensajes de error)
+// pasa por aquí antes de escribirse en el HTML.
+
+// Agrega el texto al HTML escapando los caracteres especiales.
+pub fn push_escaped(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '&' => html.push_str("&amp;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            _ => html.push(c),
+        }
+    }
+}
+
+// Devuelve el texto escapado para poder insertarlo en el HTML.
+pub fn escape(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    push_escaped(&mut html, text);
+    html
+}
+
+// Agrega un span con la clase indicada y el texto escapado.
+pub fn push_span(html: &mut String, class: &str, text: &str) {
+    html.push_str("<span class=\"");
+    push_escaped(html, class);
+    html.push_str("\">");
+    push_escaped(html, text);
+    html.push_str("</span>");
+}
+
+// Elementos que no tienen etiqueta de cierre.
+const VOID_ELEMENTS: [&str; 6] = ["br", "meta", "link", "img", "input", "hr"];
+
+// Comprueba que el HTML esté bien formado: etiquetas balanceadas, atributos entre comillas,
+// entidades válidas y ningún `<` o `>` suelto en el texto.
+pub fn check_well_formed(html: &str) -> Result<(), String> {
+    let mut open = Vec::new();
+    let mut rest = html;
+
+    while let Some(c) = rest.chars().next() {
+        let offset = html.len() - rest.len();
+
+        match c {
+            '<' if rest.starts_with("<!--") => {
+                let end = rest
+                    .find("-->")
+                    .ok_or_else(|| format!("Unclosed comment at {}", offset))?;
+                rest = &rest[end + 3..];
+            }
+            '<' => {
+                let end = tag_end(rest).ok_or_else(|| format!("Unclosed tag at {}", offset))?;
+                let tag = &rest[1..end];
+                rest = &rest[end + 1..];
+
+                if let Some(name) = tag.strip_prefix('/') {
+                    match open.pop() {
+                        Some(expected) if expected == name.trim() => {}
+                        Some(expected) => {
+                            return Err(format!(
+                                "Unexpected </{}> at {}, expected </{}>",
+                                name, offset, expected
+                            ))
+                        }
+                        None => return Err(format!("Unexpected </{}> at {}", name, offset)),
+                    }
+                } else {
+                    let name = tag
+                        .split(|c: char| c.is_whitespace() || c == '/')
+                        .next()
+                        .unwrap_or_default();
+
+                    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+                        return Err(format!("Invalid tag <{}> at {}", tag, offset));
+                    }
+
+                    let name = name.to_ascii_lowercase();
+                    if !VOID_ELEMENTS.contains(&name.as_str()) && !tag.ends_with('/') {
+                        open.push(name);
+                    }
+                }
+            }
+            '>' => return Err(format!("Unescaped '>' at {}", offset)),
+            '&' => {
+                let end = rest
+                    .find(';')
+                    .filter(|end| is_entity(&rest[1..*end]))
+                    .ok_or_else(|| format!("Invalid entity at {}", offset))?;
+                rest = &rest[end + 1..];
+            }
+            _ => rest = &rest[c.len_utf8()..],
+        }
+    }
+
+    match open.pop() {
+        Some(name) => Err(format!("Unclosed <{}>", name)),
+        None => Ok(()),
+    }
+}
+
+// Busca el `>` que cierra la etiqueta, ignorando los que están dentro de comillas.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+
+    for (i, c) in tag.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '<') => return None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn is_entity(name: &str) -> bool {
+    if let Some(number) = name.strip_prefix("#x") {
+        !number.is_empty() && number.chars().all(|c| c.is_ascii_hexdigit())
+    } else if let Some(number) = name.strip_prefix('#') {
+        !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+    } else {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric())
+    }
+}
@@ -0,0 +1,25 @@
+// Biblioteca del resaltador de sintaxis: el analizador, el árbol resultante y los
+// generadores de HTML se exponen para que otras herramientas puedan usarlos.
+pub mod analyzer;
+pub mod check;
+pub mod diagnostic;
+pub mod generate;
+pub mod html;
+pub mod line_index;
+mod lint;
+pub mod parsed;
+mod reader;
+pub mod registry;
+pub mod source;
+mod table;
+pub mod variants;
+
+pub use analyzer::{Analyzer, Error, Expression, Token};
+pub use diagnostic::{Diagnostic, Report, Severity};
+pub use generate::{
+    create_mark, highlight_parsed, highlight_str, output_path, render_errors, render_page,
+};
+pub use line_index::{LineCol, LineIndex};
+pub use parsed::{Chunk, Parsed};
+pub use registry::Registry;
+pub use source::Source;
@@ -0,0 +1,62 @@
+// Índice de líneas: se construye una vez por entrada y convierte posiciones en bytes
+// a línea y columna, empezando en 1.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineCol {
+    pub line: usize,
+    // columna contando bytes de UTF-8
+    pub column: usize,
+    // columna contando unidades de UTF-16, como la cuentan los editores y navegadores
+    pub column_utf16: usize,
+}
+
+pub struct LineIndex<'a> {
+    text: &'a str,
+    // posición donde empieza cada línea
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    // nota: "\n", "\r\n" y "\r" se consideran saltos de línea
+    pub fn new(text: &'a str) -> Self {
+        let bytes = text.as_bytes();
+        let mut starts = vec![0];
+
+        for (i, byte) in bytes.iter().enumerate() {
+            match byte {
+                b'\n' => starts.push(i + 1),
+                b'\r' if bytes.get(i + 1) != Some(&b'\n') => starts.push(i + 1),
+                _ => {}
+            }
+        }
+
+        LineIndex { text, starts }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    // línea (empezando en 1) que contiene la posición
+    pub fn line(&self, offset: usize) -> usize {
+        self.starts.partition_point(|start| *start <= offset)
+    }
+
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let offset = offset.min(self.text.len());
+        let line = self.line(offset);
+        let start = self.starts[line - 1];
+
+        // si la posición no está en el límite de un carácter se toma el inicio del carácter
+        let mut end = offset;
+        while !self.text.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        LineCol {
+            line,
+            column: offset - start + 1,
+            column_utf16: self.text[start..end].encode_utf16().count() + 1,
+        }
+    }
+}
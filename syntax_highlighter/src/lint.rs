@@ -0,0 +1,484 @@
+// Revision de la gramatica en busca de opciones que se ocultan entre si: como las opciones
+// se prueban en orden y se toma la primera que se cumple, una opcion puede no probarse
+// nunca o quedar oculta por una anterior sin que el analisis falle.
+use crate::{
+    analyzer::{internal_class, reachable, Analyzer, Expression, Fixity},
+    diagnostic::{Diagnostic, Span},
+    table::CharClass,
+};
+use std::collections::{HashMap, HashSet};
+
+type Grammar = HashMap<String, Vec<Vec<Expression>>>;
+
+// cuantas reglas se reemplazan como maximo por sus expresiones al comparar dos opciones,
+// para no seguir sin fin las reglas recursivas
+const EXPANSIONS: usize = 16;
+
+// caracter con el que puede empezar lo que reconoce una expresion
+#[derive(Debug, Clone, PartialEq)]
+enum First {
+    Char(char),
+    // una regla interna, por su nombre
+    Class(&'static str),
+    Set(Vec<(char, char)>, bool),
+}
+
+impl First {
+    // rangos ordenados y sin solaparse de los caracteres que reconoce
+    // nota: las reglas internas no tienen rangos, se consultan caracter por caracter
+    fn ranges(&self) -> Option<Vec<(char, char)>> {
+        let (ranges, negated) = match self {
+            First::Char(c) => return Some(vec![(*c, *c)]),
+            First::Class(_) => return None,
+            First::Set(ranges, negated) => (ranges, *negated),
+        };
+
+        let mut sorted: Vec<(char, char)> = ranges
+            .iter()
+            .filter(|(first, last)| first <= last)
+            .copied()
+            .collect();
+        sorted.sort();
+        let mut merged: Vec<(char, char)> = Vec::new();
+        for (first, last) in sorted {
+            match merged.last_mut() {
+                Some((_, end)) if first <= *end => *end = last.max(*end),
+                _ => merged.push((first, last)),
+            }
+        }
+        if !negated {
+            return Some(merged);
+        }
+
+        // los huecos entre los rangos, saltando los caracteres que no existen
+        let mut complement = Vec::new();
+        let mut next = Some('\0');
+        for (first, last) in merged {
+            if let Some(start) = next.filter(|start| *start < first) {
+                complement.push((start, step(first, -1)));
+            }
+            next = (last != char::MAX).then(|| step(last, 1));
+        }
+        complement.extend(next.map(|start| (start, char::MAX)));
+        Some(complement)
+    }
+
+    // un caracter con el que pueden empezar los dos, para el mensaje
+    // nota: se cruzan los rangos, y una regla interna se prueba solo dentro de los rangos
+    // del otro
+    fn overlap(&self, other: &First) -> Option<char> {
+        match (self.ranges(), other.ranges()) {
+            (Some(a), Some(b)) => {
+                let (mut i, mut j) = (0, 0);
+                while let (Some(x), Some(y)) = (a.get(i), b.get(j)) {
+                    let first = x.0.max(y.0);
+                    if first <= x.1.min(y.1) {
+                        return Some(first);
+                    }
+                    match x.1 < y.1 {
+                        true => i += 1,
+                        false => j += 1,
+                    }
+                }
+                None
+            }
+            (Some(ranges), None) => class_overlap(other, &ranges, |_| true),
+            (None, Some(ranges)) => class_overlap(self, &ranges, |_| true),
+            (None, None) => {
+                let class = class(other)?;
+                class_overlap(self, &[('\0', char::MAX)], |c| class.contains(c))
+            }
+        }
+    }
+}
+
+// el caracter siguiente o anterior, saltando los que no existen entre `\u{D7FF}` y
+// `\u{E000}`
+fn step(c: char, by: i32) -> char {
+    let next = (c as u32).wrapping_add_signed(by);
+    char::from_u32(next).unwrap_or(match by > 0 {
+        true => '\u{E000}',
+        false => '\u{D7FF}',
+    })
+}
+
+fn class(first: &First) -> Option<CharClass> {
+    match first {
+        First::Class(rule) => internal_class(rule).map(|(_, class)| class),
+        _ => None,
+    }
+}
+
+// el primer caracter de los rangos que reconoce la regla interna y cumple `also`
+// nota: los caracteres ASCII se consultan en el mapa de bits de la regla
+fn class_overlap(
+    first: &First,
+    ranges: &[(char, char)],
+    also: impl Fn(char) -> bool,
+) -> Option<char> {
+    let class = class(first)?;
+    ranges
+        .iter()
+        .flat_map(|(first, last)| *first..=*last)
+        .find(|c| class.contains(*c) && also(*c))
+}
+
+struct Lint<'a> {
+    grammar: &'a Grammar,
+    nullable: HashSet<&'a str>,
+    // reglas que siempre se cumplen, aunque sea sin consumir texto
+    infallible: HashSet<&'a str>,
+    first: HashMap<&'a str, Vec<First>>,
+    // reglas que llama cada regla sin haber consumido texto
+    calls: HashMap<&'a str, Vec<&'a str>>,
+    // donde se define cada regla, para ubicar las advertencias
+    span: &'a dyn Fn(&str) -> Option<Span>,
+    warnings: Vec<Diagnostic>,
+}
+
+// revisa la gramatica y devuelve las advertencias ordenadas por regla
+pub fn lint<'a>(grammar: &'a Grammar, span: &'a dyn Fn(&str) -> Option<Span>) -> Vec<Diagnostic> {
+    let mut lint = Lint {
+        grammar,
+        nullable: Analyzer::nullable_rules(grammar),
+        infallible: infallible_rules(grammar),
+        first: HashMap::new(),
+        calls: Analyzer::left_recursion(grammar).calls,
+        span,
+        warnings: Vec::new(),
+    };
+    lint.first = lint.first_rules();
+
+    let mut names: Vec<&String> = grammar.keys().collect();
+    names.sort();
+
+    for name in names {
+        lint.options(name, &grammar[name]);
+
+        let mut expressions = Vec::new();
+        for expression in grammar[name].iter().flatten() {
+            expression.walk(&mut |expression| expressions.push(expression));
+        }
+
+        for expression in expressions {
+            match expression {
+                Expression::Group(options) => lint.options(name, options),
+                Expression::Precedence { levels, .. } => {
+                    for (_, operators) in levels {
+                        lint.options(name, operators);
+                    }
+                }
+                // una repeticion de algo que puede no consumir texto se detiene en la
+                // primera vuelta que no avanza, por lo que casi siempre es un error
+                Expression::Repeat {
+                    expression: repeated,
+                    max: None,
+                    ..
+                } if repeated.nullable(&lint.nullable) => lint.warnings.push(Diagnostic::warning(
+                    format!(
+                        "Nullable expression under repetition in {}: {}",
+                        name, expression
+                    ),
+                    span(name),
+                )),
+                _ => {}
+            }
+        }
+    }
+
+    lint.warnings
+}
+
+impl<'a> Lint<'a> {
+    // compara cada opcion con las anteriores
+    // nota: se reporta una advertencia por opcion, la mas grave entre todas las anteriores
+    fn options(&mut self, rule: &str, options: &'a [Vec<Expression>]) {
+        for (j, option) in options.iter().enumerate() {
+            let previous = &options[..j];
+
+            let warning = if let Some(i) = previous
+                .iter()
+                .position(|previous| previous.iter().all(|e| infallible(e, &self.infallible)))
+            {
+                format!(
+                    "Unreachable alternative {} in {}: alternative {} always matches",
+                    j + 1,
+                    rule,
+                    i + 1
+                )
+            } else if let Some(i) = previous
+                .iter()
+                .position(|previous| self.prefix(previous, option))
+            {
+                format!(
+                    "Shadowed alternative {} in {}: alternative {} matches a prefix of it",
+                    j + 1,
+                    rule,
+                    i + 1
+                )
+            } else if let Some((i, c)) = previous
+                .iter()
+                .enumerate()
+                .filter(|_| !self.left_recursive(rule, option))
+                .filter(|(_, previous)| !self.left_recursive(rule, previous))
+                .filter(|(_, previous)| !self.keyword(previous))
+                .find_map(|(i, previous)| Some((i, self.overlap(previous, option)?)))
+            {
+                format!(
+                    "Ambiguous alternatives {} and {} in {}: both can start with {:?}",
+                    i + 1,
+                    j + 1,
+                    rule,
+                    c
+                )
+            } else {
+                continue;
+            };
+
+            self.warnings
+                .push(Diagnostic::warning(warning, (self.span)(rule)));
+        }
+    }
+
+    // si lo que reconoce la opcion anterior es el inicio de lo que reconoce la otra: las
+    // mismas expresiones al principio, o un string que empieza por el string anterior
+    fn prefix(&self, previous: &'a [Expression], option: &'a [Expression]) -> bool {
+        match self.strip(previous, option) {
+            (previous, _) if previous.is_empty() => true,
+            (previous, option) => match (previous.as_slice(), option.last()) {
+                ([Expression::String(previous)], Some(Expression::String(text))) => {
+                    text.starts_with(previous.as_str())
+                }
+                _ => false,
+            },
+        }
+    }
+
+    // quita lo que las dos opciones tienen igual al principio y devuelve lo que queda de
+    // cada una, al reves para sacar las expresiones del final
+    // nota: las reglas de una sola opcion se reemplazan por sus expresiones, asi en
+    // `number | float` se ve que `float` empieza con `number`
+    fn strip(
+        &self,
+        previous: &'a [Expression],
+        option: &'a [Expression],
+    ) -> (Vec<&'a Expression>, Vec<&'a Expression>) {
+        let stack = |option: &'a [Expression]| -> Vec<&'a Expression> {
+            let option = option.iter().rev();
+            option
+                .filter(|e| !matches!(e, Expression::Label(_)))
+                .collect()
+        };
+        let (mut previous, mut option) = (stack(previous), stack(option));
+
+        for _ in 0..=EXPANSIONS {
+            while let (Some(a), Some(b)) = (previous.last(), option.last()) {
+                if a.to_string() != b.to_string() {
+                    break;
+                }
+                previous.pop();
+                option.pop();
+            }
+
+            // se reemplaza la regla de alguno de los dos lados y se vuelve a comparar
+            let side = [&mut previous, &mut option]
+                .into_iter()
+                .find_map(|side| Some((self.expand(side.last()?)?, side)));
+            let Some((expansion, side)) = side else { break };
+            side.pop();
+            side.extend(stack(expansion));
+        }
+
+        (previous, option)
+    }
+
+    // las expresiones de una regla de una sola opcion
+    fn expand(&self, expression: &Expression) -> Option<&'a [Expression]> {
+        match expression {
+            Expression::Rule(rule) => match self.grammar.get(rule)?.as_slice() {
+                [option] => Some(option),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // si la opcion empieza con una keyword que es una palabra: solo se cumple con la palabra
+    // completa, por lo que va antes que los identificadores a proposito
+    fn keyword(&self, option: &'a [Expression]) -> bool {
+        let mut option = option;
+        for _ in 0..EXPANSIONS {
+            match option.first() {
+                Some(Expression::Keyword(rule)) => {
+                    let keyword = self.grammar.get(rule).and_then(|options| options.first());
+                    return match keyword.map(Vec::as_slice) {
+                        Some([Expression::String(text)]) => text
+                            .chars()
+                            .last()
+                            .is_some_and(unicode_ident::is_xid_continue),
+                        _ => false,
+                    };
+                }
+                Some(expression) => match self.expand(expression) {
+                    Some(expansion) => option = expansion,
+                    None => return false,
+                },
+                None => return false,
+            }
+        }
+
+        false
+    }
+
+    // si la opcion empieza llamando a la regla, como `expression ~ "+" ~ term`, o a otra
+    // que vuelve a llamarla sin consumir texto
+    // nota: las opciones recursivas por la izquierda empiezan como las que no lo son a
+    // proposito, porque la regla crece a partir del resultado de estas
+    fn left_recursive(&self, rule: &str, option: &[Expression]) -> bool {
+        let mut calls = Vec::new();
+        Expression::left_calls(option, &self.nullable, &mut calls);
+        calls
+            .iter()
+            .any(|call| *call == rule || reachable(&self.calls, call).contains(rule))
+    }
+
+    // un caracter con el que pueden empezar las dos opciones despues de lo que tienen igual
+    // nota: si la otra opcion es el inicio de la anterior no hay ambiguedad, la anterior es
+    // mas larga y falla con lo que la otra reconoce, como en `float | number`
+    fn overlap(&self, previous: &'a [Expression], option: &'a [Expression]) -> Option<char> {
+        let (previous, option) = self.strip(previous, option);
+        if option.is_empty() {
+            return None;
+        }
+
+        let previous = self.first_option(previous.into_iter().rev());
+        let option = self.first_option(option.into_iter().rev());
+        previous
+            .iter()
+            .flat_map(|a| option.iter().map(move |b| (a, b)))
+            .find_map(|(a, b)| a.overlap(b))
+    }
+
+    // primeros caracteres de cada regla
+    // nota: se recalculan todas las reglas hasta que ninguna agrega caracteres, porque una
+    // regla puede depender de otras que todavia no se calcularon
+    fn first_rules(&mut self) -> HashMap<&'a str, Vec<First>> {
+        loop {
+            let mut changed = false;
+            for (name, options) in self.grammar {
+                let mut first = Vec::new();
+                for option in options {
+                    for c in self.first_option(option) {
+                        if !first.contains(&c) {
+                            first.push(c);
+                        }
+                    }
+                }
+
+                if first.len() != self.first.get(name.as_str()).map_or(0, Vec::len) {
+                    self.first.insert(name, first);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                return std::mem::take(&mut self.first);
+            }
+        }
+    }
+
+    // primeros caracteres de una opcion: los de cada expresion mientras las anteriores
+    // puedan no consumir texto
+    fn first_option<'b>(&self, option: impl IntoIterator<Item = &'b Expression>) -> Vec<First> {
+        let mut first = Vec::new();
+        for expression in option {
+            self.first_expression(expression, &mut first);
+            if !expression.nullable(&self.nullable) {
+                break;
+            }
+        }
+        first
+    }
+
+    fn first_expression(&self, expression: &Expression, first: &mut Vec<First>) {
+        match expression {
+            Expression::String(text) => first.extend(text.chars().next().map(First::Char)),
+            Expression::Keyword(rule) => {
+                if let Some(Expression::String(text)) = self
+                    .grammar
+                    .get(rule)
+                    .and_then(|options| options.first())
+                    .and_then(|option| option.first())
+                {
+                    first.extend(text.chars().next().map(First::Char));
+                }
+            }
+            Expression::Rule(rule) => {
+                if let Some(rule) = self.first.get(rule.as_str()) {
+                    first.extend(rule.iter().cloned());
+                }
+            }
+            Expression::InternalRule(rule) => {
+                first.extend(internal_class(rule).map(|(rule, _)| First::Class(rule)))
+            }
+            Expression::Set { ranges, negated } => first.push(First::Set(ranges.clone(), *negated)),
+            Expression::Repeat { expression, .. } => self.first_expression(expression, first),
+            Expression::Group(options) => {
+                for option in options {
+                    first.extend(self.first_option(option));
+                }
+            }
+            // el operando o un operador prefijo
+            Expression::Precedence { operand, levels } => {
+                self.first_expression(operand, first);
+                for (_, operators) in levels.iter().filter(|(f, _)| *f == Fixity::Prefix) {
+                    for option in operators {
+                        first.extend(self.first_option(option));
+                    }
+                }
+            }
+            // los predicados no consumen texto
+            Expression::Predicate { .. } | Expression::Label(_) => {}
+        }
+    }
+}
+
+// si la expresion siempre se cumple, sabiendo que reglas siempre se cumplen
+fn infallible(expression: &Expression, rules: &HashSet<&str>) -> bool {
+    match expression {
+        Expression::String(text) => text.is_empty(),
+        Expression::Rule(rule) => rules.contains(rule.as_str()),
+        Expression::InternalRule(rule) => internal_class(rule).is_none(),
+        Expression::Repeat {
+            expression, min, ..
+        } => *min == 0 || infallible(expression, rules),
+        Expression::Group(options) => options
+            .iter()
+            .any(|option| option.iter().all(|e| infallible(e, rules))),
+        Expression::Label(_) => true,
+        Expression::Precedence { operand, .. } => infallible(operand, rules),
+        Expression::Keyword(_) | Expression::Set { .. } | Expression::Predicate { .. } => false,
+    }
+}
+
+// nota: como con las reglas que pueden no consumir texto, se agregan reglas hasta que
+// ninguna opcion cambia de resultado
+fn infallible_rules(grammar: &Grammar) -> HashSet<&str> {
+    let mut rules = HashSet::new();
+
+    loop {
+        let length = rules.len();
+        for (name, options) in grammar {
+            if options
+                .iter()
+                .any(|option| option.iter().all(|e| infallible(e, &rules)))
+            {
+                rules.insert(name.as_str());
+            }
+        }
+
+        if rules.len() == length {
+            return rules;
+        }
+    }
+}
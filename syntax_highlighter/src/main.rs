@@ -1,55 +1,403 @@
-use analyzer::Analyzer;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use syntax_highlighter::{
+    analyzer::MemoStats, check, highlight_parsed, output_path, render_errors, variants, Analyzer,
+    Registry, Source,
+};
 
-use std::{collections::VecDeque, fs, time::Instant};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
-// Declaramos los módulos que usaremos en este archivo.
-mod analyzer;
-mod generate;
-mod parsed;
-mod utils;
-mod variants;
+// Cantidad de fragmentos del corpus que forman cada entrada aleatoria.
+const RANDOM_FRAGMENTS: usize = 64;
 
-// Constante que almacena la gramática cargada desde un archivo de texto.
-const GRAMMAR: &'static str = include_str!("grammar.txt");
+// Interfaz de línea de comandos del resaltador de sintaxis.
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Analiza código fuente y genera su representación en HTML"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Analiza los archivos de entrada y genera un HTML por cada uno
+    Highlight(HighlightArgs),
+    /// Valida las gramáticas sin procesar ningún archivo
+    Validate(GrammarArgs),
+    /// Comprueba con un corpus de entradas que el HTML esté bien formado y que el
+    /// árbol conserve todo el texto
+    Check(CheckArgs),
+    /// Mide el tiempo de análisis con y sin memoización
+    Bench(BenchArgs),
+}
+
+// Opciones para cargar las gramáticas, compartidas por todos los subcomandos.
+#[derive(Args)]
+struct GrammarArgs {
+    /// Archivo de gramática por defecto
+    #[arg(short, long, default_value = "src/grammar.txt")]
+    grammar: PathBuf,
+
+    /// Regla inicial de la gramática
+    #[arg(short, long, default_value = "program")]
+    rule: String,
+
+    /// Extensión de los archivos que usan la gramática por defecto
+    #[arg(short, long, default_value = "txt")]
+    extension: String,
+
+    /// Gramática adicional para otra extensión, con la forma EXT=ARCHIVO
+    #[arg(short, long = "language", value_name = "EXT=ARCHIVO")]
+    languages: Vec<String>,
+
+    /// Guarda el resultado de cada regla en cada posición para no repetir el análisis
+    #[arg(long)]
+    memo: bool,
+}
+
+#[derive(Args)]
+struct HighlightArgs {
+    /// Archivos, carpetas o patrones glob a procesar
+    #[arg(default_value = "test")]
+    inputs: Vec<String>,
+
+    /// Carpeta donde se escriben los HTML generados
+    #[arg(short, long, default_value = "results")]
+    output: PathBuf,
+
+    /// Plantilla HTML donde se inserta el contenido
+    #[arg(short, long, default_value = "src/template.html")]
+    template: PathBuf,
+
+    /// Número máximo de archivos a procesar
+    #[arg(short = 'n', long)]
+    limit: Option<usize>,
+
+    /// Forma de procesar los archivos
+    #[arg(short, long, value_enum, default_value_t = Mode::Parallel)]
+    mode: Mode,
+
+    #[command(flatten)]
+    grammar: GrammarArgs,
+}
+
+#[derive(Args)]
+struct CheckArgs {
+    /// Archivos, carpetas o patrones glob del corpus
+    #[arg(default_value = "corpus")]
+    inputs: Vec<String>,
+
+    /// Número de entradas aleatorias que se comprueban además del corpus
+    #[arg(long, default_value_t = 1000)]
+    random: u64,
+
+    /// Semilla para generar las entradas aleatorias
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+
+    #[command(flatten)]
+    grammar: GrammarArgs,
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    /// Archivos, carpetas o patrones glob a analizar
+    #[arg(default_value = "corpus")]
+    inputs: Vec<String>,
+
+    /// Número de entradas aleatorias que se analizan además de los archivos
+    #[arg(long, default_value_t = 0)]
+    random: u64,
+
+    /// Semilla para generar las entradas aleatorias
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+
+    /// Número máximo de archivos a analizar
+    #[arg(short = 'n', long)]
+    limit: Option<usize>,
+
+    #[command(flatten)]
+    grammar: GrammarArgs,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Mode {
+    Parallel,
+    Sequential,
+}
 
 // La función main dirige el flujo principal de ejecución del programa.
 fn main() -> anyhow::Result<()> {
-    // Crea una instancia del analizador con la gramática y la regla inicial.
-    let analyzer = Analyzer::new(GRAMMAR, "program")?;
-    analyzer.validate()?;
+    match Cli::parse().command {
+        Command::Highlight(args) => highlight(args),
+        Command::Validate(args) => {
+            let report = load_registry(&args)?.report();
+            print!("{}", report.text);
+            if report.errors > 0 {
+                return Err(anyhow::anyhow!(
+                    "Invalid grammar: {} errors, {} warnings",
+                    report.errors,
+                    report.warnings
+                ));
+            }
+            println!("Gramática válida, con {} advertencias", report.warnings);
+            Ok(())
+        }
+        Command::Check(args) => check(args),
+        Command::Bench(args) => bench(args),
+    }
+}
+
+fn highlight(args: HighlightArgs) -> anyhow::Result<()> {
+    // Carga las gramáticas de cada extensión con la regla inicial.
+    let registry = load_registry(&args.grammar)?;
+    registry.validate()?;
+
+    // Obtiene los archivos a procesar, limitados a la cantidad indicada.
+    let extensions: Vec<&str> = registry.extensions().collect();
+    let mut files_queue = get_files_queue(&args.inputs, &extensions)?;
+    if let Some(limit) = args.limit {
+        files_queue.truncate(limit);
+    }
+    println!("Archivos encontrados: {}", files_queue.len());
 
-    // Llama a la función que procesa los archivos en la cola.
-    let files_queue = get_files_queue()?;
-    let n = 1000;
-    println!("Archivos encontrados: {}", n);
+    // Dos archivos con el mismo nombre en carpetas distintas escribirían el mismo HTML.
+    let mut outputs = HashMap::new();
+    for path in &files_queue {
+        if let Some(other) = outputs.insert(output_path(path, &args.output), path) {
+            return Err(anyhow::anyhow!(
+                "{} y {} se escribirían en el mismo archivo de salida",
+                other.display(),
+                path.display()
+            ));
+        }
+    }
 
-    let template = fs::read_to_string("src/template.html")?;
+    let template = fs::read_to_string(&args.template)?;
+
+    // Crea la carpeta de salida si no existe.
+    fs::create_dir_all(&args.output)?;
 
     // Inicia el temporizador.
     let start_time = Instant::now();
 
-    // Procesa cada archivo en la cola en paralelo.
-    variants::parallel(files_queue, &analyzer, &template, n);
-
-    // Procesa cada archivo en la cola de forma secuencial.
-    // variants::sequential(files_queue, &analyzer, &template, n);
+    let failures = match args.mode {
+        // Procesa cada archivo en la cola en paralelo.
+        Mode::Parallel => {
+            println!("Procesando en paralelo...");
+            variants::parallel(&files_queue, &registry, &template, &args.output)
+        }
+        // Procesa cada archivo en la cola de forma secuencial.
+        Mode::Sequential => {
+            println!("Procesando de forma secuencial...");
+            variants::sequential(&files_queue, &registry, &template, &args.output)
+        }
+    };
 
     // Detiene el temporizador y calcula la duración.
     let duration = start_time.elapsed();
     println!("Tiempo de ejecución: {:?}", duration);
 
+    for failure in &failures {
+        println!("{}", failure);
+    }
+    if !failures.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} archivos no se pudieron procesar",
+            failures.len()
+        ));
+    }
+
+    Ok(())
+}
+
+fn check(args: CheckArgs) -> anyhow::Result<()> {
+    let registry = load_registry(&args.grammar)?;
+    let extensions: Vec<&str> = registry.extensions().collect();
+    let files_queue = get_files_queue(&args.inputs, &extensions)?;
+    let mut failures = 0;
+
+    // Comprueba cada archivo del corpus con la gramática de su extensión.
+    for path in &files_queue {
+        let input = fs::read_to_string(path)?;
+        let analyzer = registry.get(path);
+        if let Err(e) = check::check_html(analyzer, &input)
+            .and_then(|_| check::check_round_trip(analyzer, &input))
+        {
+            println!("{}: {}", path.display(), e);
+            failures += 1;
+        }
+    }
+
+    // Comprueba entradas arbitrarias con la gramática por defecto.
+    let analyzer = registry.get(Path::new(""));
+    for seed in args.seed..args.seed + args.random {
+        let input = check::random_input(seed, RANDOM_FRAGMENTS);
+        if let Err(e) = check::check_html(analyzer, &input)
+            .and_then(|_| check::check_round_trip(analyzer, &input))
+        {
+            println!("semilla {}: {}", seed, e);
+            failures += 1;
+        }
+    }
+
+    let total = files_queue.len() as u64 + args.random;
+    println!("Entradas comprobadas: {}, con errores: {}", total, failures);
+
+    if failures > 0 {
+        return Err(anyhow::anyhow!(
+            "{} entradas no pasan la comprobación",
+            failures
+        ));
+    }
+
+    Ok(())
+}
+
+fn bench(args: BenchArgs) -> anyhow::Result<()> {
+    let mut registry = load_registry(&args.grammar)?;
+    let extensions: Vec<&str> = registry.extensions().collect();
+    let mut files_queue = get_files_queue(&args.inputs, &extensions)?;
+    if let Some(limit) = args.limit {
+        files_queue.truncate(limit);
+    }
+    println!("Archivos encontrados: {}", files_queue.len());
+
+    // Las entradas aleatorias se analizan con la gramática por defecto, como en `check`.
+    let mut inputs = Vec::new();
+    for path in &files_queue {
+        inputs.push((
+            path.display().to_string(),
+            path.clone(),
+            fs::read_to_string(path)?,
+        ));
+    }
+    for seed in args.seed..args.seed + args.random {
+        let input = check::random_input(seed, RANDOM_FRAGMENTS);
+        inputs.push((format!("semilla {}", seed), PathBuf::new(), input));
+    }
+
+    let mut durations = [Duration::ZERO; 2];
+    let mut stats = MemoStats::default();
+    let mut differences = 0;
+
+    for (name, path, input) in &inputs {
+        let source = Source::new(input);
+        let mut pages = Vec::new();
+
+        // Analiza cada entrada sin y con memoización, en ese orden.
+        for (i, memoize) in [false, true].into_iter().enumerate() {
+            registry.set_memoize(memoize);
+            let analyzer = registry.get(path);
+
+            let start_time = Instant::now();
+            let (parsed, memo) = analyzer.parse_with_stats(&source.text);
+            durations[i] += start_time.elapsed();
+
+            stats.entries += memo.entries;
+            stats.hits += memo.hits;
+            stats.bytes += memo.bytes;
+            pages
+                .push(highlight_parsed(&source, &parsed) + &render_errors(&source, &parsed.errors));
+        }
+
+        // La memoización no debe cambiar el resultado.
+        if pages[0] != pages[1] {
+            println!("{}: el resultado cambia con memoización", name);
+            differences += 1;
+        }
+    }
+
+    let files = inputs.len().max(1);
+    println!("Sin memoización: {:?}", durations[0]);
+    println!(
+        "Con memoización: {:?} ({:.2}x)",
+        durations[1],
+        durations[0].as_secs_f64() / durations[1].as_secs_f64().max(f64::EPSILON)
+    );
+    println!(
+        "Tabla de memoización por entrada: {} resultados, {} reutilizados, {:.1} KiB",
+        stats.entries / files,
+        stats.hits / files,
+        stats.bytes as f64 / files as f64 / 1024.0
+    );
+
+    if differences > 0 {
+        return Err(anyhow::anyhow!(
+            "{} entradas cambian con memoización",
+            differences
+        ));
+    }
+
     Ok(())
 }
 
-fn get_files_queue() -> anyhow::Result<VecDeque<std::fs::DirEntry>> {
-    // Crea una cola de archivos de texto en la carpeta "multipletext".
-    let files_queue: VecDeque<_> = fs::read_dir("test")?
-        .filter_map(Result::ok)
-        .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "txt"))
-        .collect();
+// Carga la gramática por defecto y las gramáticas adicionales de cada extensión.
+fn load_registry(args: &GrammarArgs) -> anyhow::Result<Registry> {
+    let analyzer = Analyzer::from_file(&args.grammar, &args.rule)?;
+    let mut registry = Registry::new(&args.extension, analyzer);
+
+    for language in &args.languages {
+        let (extension, path) = language.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!("Lenguaje inválido, se esperaba EXT=ARCHIVO: {}", language)
+        })?;
+        registry.register(extension, Analyzer::from_file(path, &args.rule)?);
+    }
+
+    registry.set_memoize(args.memo);
+
+    Ok(registry)
+}
+
+// Resuelve las entradas (archivos, carpetas o patrones glob) en una lista ordenada
+// de archivos sin repetidos.
+fn get_files_queue(inputs: &[String], extensions: &[&str]) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = BTreeSet::new();
+
+    for input in inputs {
+        let path = Path::new(input);
+
+        if path.is_dir() {
+            // de las carpetas solo se toman los archivos con una gramática registrada
+            for entry in fs::read_dir(path)?.filter_map(Result::ok) {
+                let path = entry.path();
+                let registered = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| extensions.contains(&ext));
+
+                if path.is_file() && registered {
+                    files.insert(path);
+                }
+            }
+        } else if path.is_file() {
+            files.insert(path.to_path_buf());
+        } else {
+            // si no existe como ruta se interpreta como un patrón glob
+            let mut matched = false;
+            for path in glob::glob(input)?.filter_map(Result::ok) {
+                if path.is_file() {
+                    files.insert(path);
+                    matched = true;
+                }
+            }
 
-    // Crea la carpeta "results" si no existe.
-    fs::create_dir_all("results")?;
+            if !matched {
+                return Err(anyhow::anyhow!(
+                    "No se encontraron archivos para: {}",
+                    input
+                ));
+            }
+        }
+    }
 
-    Ok(files_queue)
+    Ok(files.into_iter().collect())
 }
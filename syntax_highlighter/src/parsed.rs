@@ -1,9 +1,8 @@
 use crate::analyzer::{Error, Token};
 
 // el Chunk se usa para dar el valor de un token y sus hijos
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Chunk<'a, 'b> {
-    #[allow(dead_code)]
     input: &'a str,
     pub token: &'b Token<'a>,
 }
@@ -13,7 +12,7 @@ pub struct Chunk<'a, 'b> {
 pub struct Parsed<'a> {
     input: &'a str,
     pub token: Token<'a>,
-    pub errors: Vec<Error<'a>>,
+    pub errors: Vec<Error>,
 }
 
 // función recursiva para recorrer los tokens
@@ -26,35 +25,46 @@ where
 
     // si el token tiene hijos, se recorren de manera recursiva
     if let (_, Some(children)) = &token.1 {
-        for child in children {
+        for child in children.iter() {
             recursive_visit(input, child, f);
         }
     }
 }
 
 impl<'a, 'b> Chunk<'a, 'b> {
-    #[allow(dead_code)]
     pub fn rule(&self) -> &'a str {
-        &self.token.0
+        self.token.0
     }
 
-    #[allow(dead_code)]
     pub fn position(&self) -> &(usize, usize) {
         &self.token.1 .0
     }
 
-    #[allow(dead_code)]
     pub fn value(&self) -> &'a str {
         let position = self.position();
         &self.input[position.0..position.1]
     }
 
-    #[allow(dead_code)]
     pub fn children(&self) -> Option<&Vec<Token<'a>>> {
-        self.token.1 .1.as_ref()
+        self.token.1 .1.as_deref()
+    }
+
+    // los hijos del token como chunks, para recorrerlos conservando la entrada
+    pub fn child_chunks(&self) -> impl Iterator<Item = Chunk<'a, 'b>> {
+        let input = self.input;
+        self.token
+            .1
+             .1
+            .iter()
+            .flat_map(|children| children.iter())
+            .map(move |token| Chunk { input, token })
+    }
+
+    // un token es hoja cuando no tiene hijos; el texto de las hojas cubre toda la entrada
+    pub fn is_leaf(&self) -> bool {
+        self.children().is_none_or(|children| children.is_empty())
     }
 
-    #[allow(dead_code)]
     pub fn visit<'c, F>(&'c self, f: &mut F)
     where
         F: FnMut(&Chunk<'a, 'c>),
@@ -64,7 +74,7 @@ impl<'a, 'b> Chunk<'a, 'b> {
 }
 
 impl<'a> Parsed<'a> {
-    pub fn new(input: &'a str, token: Token<'a>, errors: Vec<Error<'a>>) -> Self {
+    pub fn new(input: &'a str, token: Token<'a>, errors: Vec<Error>) -> Self {
         Parsed {
             input,
             token,
@@ -72,11 +82,33 @@ impl<'a> Parsed<'a> {
         }
     }
 
-    #[allow(dead_code)]
     pub fn visit<'b, F>(&'b self, f: &mut F)
     where
         F: FnMut(&Chunk<'a, 'b>),
     {
         recursive_visit(self.input, &self.token, f);
     }
+
+    pub fn root(&self) -> Chunk<'a, '_> {
+        Chunk {
+            input: self.input,
+            token: &self.token,
+        }
+    }
+
+    // las hojas del árbol en el orden de la entrada
+    pub fn leaves(&self) -> Vec<Chunk<'a, '_>> {
+        let mut leaves = Vec::new();
+        self.visit(&mut |chunk| {
+            if chunk.is_leaf() {
+                leaves.push(*chunk);
+            }
+        });
+        leaves
+    }
+
+    // reconstruye el texto concatenando las hojas; debe ser igual a la entrada
+    pub fn text(&self) -> String {
+        self.leaves().iter().map(|chunk| chunk.value()).collect()
+    }
 }
@@ -0,0 +1,563 @@
+// Lectura del formato de las gramáticas: el texto se divide en tokens (nombres, strings,
+// conjuntos y símbolos), saltando los espacios y los comentarios `//`, y cada regla se lee
+// de sus tokens. Una regla empieza en `nombre =` y sigue hasta la siguiente, por lo que
+// puede ocupar varias líneas.
+use crate::{
+    analyzer::{Expression, Fixity, Modifier},
+    diagnostic::{Diagnostic, Span},
+};
+use std::collections::HashSet;
+
+const PRECEDENCE: &str = "PRECEDENCE";
+
+// caracteres que terminan un nombre
+const SYMBOLS: &str = "=|~()[]{};@&!*+?\"'";
+
+#[derive(Debug)]
+enum Kind {
+    Name,
+    String(String),
+    Set {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    // `_{`, `@{` o `${` al inicio de las opciones
+    Modifier(Modifier),
+    Symbol(char),
+    // texto que no forma un token, con el mensaje que se reporta si se llega a leer
+    Invalid(&'static str),
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: Kind,
+    span: Span,
+}
+
+// regla leida de la gramatica
+pub struct Definition {
+    pub name: String,
+    // desde el nombre hasta el final de las opciones
+    pub span: Span,
+    pub modifier: Option<Modifier>,
+    pub options: Vec<Vec<Expression>>,
+}
+
+// lee todas las reglas de la gramatica
+// nota: una regla con un error se reporta y queda sin opciones, y se sigue con la
+// siguiente para reportar todos los errores juntos; asi sus usos no se reportan como
+// reglas no definidas
+pub fn read(text: &str) -> (Vec<Definition>, Vec<Diagnostic>) {
+    let tokens = tokenize(text);
+    let mut diagnostics = Vec::new();
+
+    // cada `=` define una regla con el nombre que lo precede en la misma linea; el nombre
+    // son los tokens pegados entre si, lo que admite nombres como `instruction*`
+    let mut starts = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if !matches!(token.kind, Kind::Symbol('=')) {
+            continue;
+        }
+
+        // nota: en `a = = b` el segundo `=` no tiene nombre
+        let mut start = i;
+        if i > 0
+            && !matches!(tokens[i - 1].kind, Kind::Symbol('='))
+            && !text[tokens[i - 1].span.1..token.span.0].contains('\n')
+        {
+            start -= 1;
+            while start > 0
+                && !matches!(tokens[start - 1].kind, Kind::Symbol('='))
+                && tokens[start - 1].span.1 == tokens[start].span.0
+            {
+                start -= 1;
+            }
+        }
+
+        if start == i {
+            diagnostics.push(Diagnostic::error(
+                "Expected a rule name before `=`".to_string(),
+                Some(token.span),
+            ));
+            continue;
+        }
+
+        starts.push((start, i));
+    }
+
+    let first = starts.first().map_or(tokens.len(), |(start, _)| *start);
+    if let Some(token) = tokens[..first].first() {
+        diagnostics.push(Diagnostic::error(
+            format!(
+                "Expected a rule definition, found {}",
+                describe(text, token)
+            ),
+            Some(token.span),
+        ));
+    }
+
+    // las reglas definidas se conocen antes de leer las opciones para distinguir
+    // una regla llamada `a*` del operador `*` aplicado a `a`
+    let name = |start: usize, equal: usize| &text[tokens[start].span.0..tokens[equal - 1].span.1];
+    let rules: HashSet<&str> = starts
+        .iter()
+        .map(|(start, equal)| name(*start, *equal))
+        .collect();
+
+    let mut definitions = Vec::new();
+    for (j, (start, equal)) in starts.iter().enumerate() {
+        let end = starts.get(j + 1).map_or(tokens.len(), |(next, _)| *next);
+        let mut parser = Parser {
+            text,
+            tokens: &tokens[equal + 1..end],
+            position: 0,
+            rules: &rules,
+            end: tokens[end - 1].span.1,
+        };
+
+        let (modifier, options) = parser.definition().unwrap_or_else(|diagnostic| {
+            diagnostics.push(diagnostic);
+            (None, Vec::new())
+        });
+        definitions.push(Definition {
+            name: name(*start, *equal).to_string(),
+            span: (tokens[*start].span.0, tokens[end - 1].span.1),
+            modifier,
+            options,
+        });
+    }
+
+    (definitions, diagnostics)
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+
+        if c.is_whitespace() {
+            i += c.len_utf8();
+            continue;
+        }
+
+        // los comentarios llegan hasta el final de la linea
+        if rest.starts_with("//") {
+            i += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        }
+
+        let (kind, length) = match c {
+            '"' => match quoted(rest, '"') {
+                Some(length) => match unescape(&rest[1..length - 1]) {
+                    Some(text) => (Kind::String(text), length),
+                    None => (Kind::Invalid("Invalid escape sequence in string"), length),
+                },
+                None => (Kind::Invalid("Unterminated string"), line_length(rest)),
+            },
+            '[' => match set_length(rest) {
+                Some(length) => {
+                    let inner = &rest[1..length - 1];
+                    let (negated, inner) = match inner.strip_prefix('^') {
+                        Some(inner) => (true, inner),
+                        None => (false, inner),
+                    };
+                    match parse_set(inner) {
+                        Some(ranges) => (Kind::Set { ranges, negated }, length),
+                        None => (Kind::Invalid("Invalid character set"), length),
+                    }
+                }
+                None => (
+                    Kind::Invalid("Unterminated character set"),
+                    line_length(rest),
+                ),
+            },
+            '\'' => (
+                Kind::Invalid("Characters between single quotes are only valid in sets"),
+                quoted(rest, '\'').unwrap_or_else(|| line_length(rest)),
+            ),
+            '_' | '@' | '$' if rest[1..].starts_with('{') => {
+                let modifier = match c {
+                    '_' => Modifier::Silent,
+                    '@' => Modifier::Atomic,
+                    _ => Modifier::CompoundAtomic,
+                };
+                (Kind::Modifier(modifier), 2)
+            }
+            _ if SYMBOLS.contains(c) => (Kind::Symbol(c), 1),
+            _ => {
+                let length = rest
+                    .char_indices()
+                    .find(|(j, c)| {
+                        c.is_whitespace() || SYMBOLS.contains(*c) || rest[*j..].starts_with("//")
+                    })
+                    .map_or(rest.len(), |(j, _)| j);
+                (Kind::Name, length)
+            }
+        };
+
+        tokens.push(Token {
+            kind,
+            span: (i, i + length),
+        });
+        i += length;
+    }
+
+    tokens
+}
+
+// largo de un string o un caracter, con las comillas, si se cierra en la misma linea
+fn quoted(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+
+    text.char_indices().skip(1).find_map(|(i, c)| match c {
+        '\n' => Some(None),
+        _ if escaped => {
+            escaped = false;
+            None
+        }
+        '\\' => {
+            escaped = true;
+            None
+        }
+        _ if c == quote => Some(Some(i + 1)),
+        _ => None,
+    })?
+}
+
+// largo de un conjunto con los corchetes, sin cortar en un `]` entre comillas
+fn set_length(text: &str) -> Option<usize> {
+    let mut i = 1;
+
+    while let Some(c) = text[i..].chars().next() {
+        match c {
+            '\'' => i += quoted(&text[i..], '\'')?,
+            ']' => return Some(i + 1),
+            '\n' => return None,
+            _ => i += c.len_utf8(),
+        }
+    }
+
+    None
+}
+
+fn line_length(text: &str) -> usize {
+    text.find('\n').unwrap_or(text.len())
+}
+
+// como se muestra un token en los mensajes
+fn describe(text: &str, token: &Token) -> String {
+    format!("`{}`", &text[token.span.0..token.span.1])
+}
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+struct Parser<'a> {
+    text: &'a str,
+    // tokens de las opciones de una regla
+    tokens: &'a [Token],
+    position: usize,
+    rules: &'a HashSet<&'a str>,
+    // donde terminan las opciones, para los errores al final de la regla
+    end: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    // avanza si el siguiente token es el simbolo
+    fn symbol(&mut self, symbol: char) -> bool {
+        let found =
+            matches!(self.peek(), Some(Token { kind: Kind::Symbol(c), .. }) if *c == symbol);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    // error en el siguiente token, o al final de la regla si no quedan
+    // nota: los tokens invalidos se reportan con su propio mensaje
+    fn error(&self, expected: &str) -> Diagnostic {
+        match self.peek() {
+            Some(Token {
+                kind: Kind::Invalid(message),
+                span,
+            }) => Diagnostic::error(message.to_string(), Some(*span)),
+            Some(token) => Diagnostic::error(
+                format!(
+                    "Expected {}, found {}",
+                    expected,
+                    describe(self.text, token)
+                ),
+                Some(token.span),
+            ),
+            None => Diagnostic::error(
+                format!("Expected {}, found end of rule", expected),
+                Some((self.end, self.end)),
+            ),
+        }
+    }
+
+    fn expect(&mut self, symbol: char, expected: &str) -> Result<()> {
+        match self.symbol(symbol) {
+            true => Ok(()),
+            false => Err(self.error(expected)),
+        }
+    }
+
+    // comprueba que no queden tokens despues de las opciones
+    fn finish(&self, expected: &str) -> Result<()> {
+        match self.peek() {
+            Some(_) => Err(self.error(&format!("`~`, {} or end of rule", expected))),
+            None => Ok(()),
+        }
+    }
+
+    // las opciones de una regla, con su modificador si tiene uno
+    fn definition(&mut self) -> Result<(Option<Modifier>, Vec<Vec<Expression>>)> {
+        let modifier = match self.peek() {
+            Some(Token {
+                kind: Kind::Modifier(modifier),
+                ..
+            }) => Some(*modifier),
+            _ => None,
+        };
+
+        if modifier.is_none() {
+            let options = self.options()?;
+            self.finish("`|`")?;
+            return Ok((None, options));
+        }
+
+        self.position += 1;
+        let options = self.options()?;
+        self.expect('}', "`~`, `|` or `}`")?;
+        self.finish("`|`")?;
+        Ok((modifier, options))
+    }
+
+    // opciones separadas por `|`
+    fn options(&mut self) -> Result<Vec<Vec<Expression>>> {
+        let mut options = vec![self.sequence()?];
+        while self.symbol('|') {
+            options.push(self.sequence()?);
+        }
+        Ok(options)
+    }
+
+    // expresiones separadas por `~`, que pueden terminar en el mensaje de sus errores
+    fn sequence(&mut self) -> Result<Vec<Expression>> {
+        let mut expressions = vec![self.element()?];
+        while self.symbol('~') {
+            expressions.push(self.element()?);
+        }
+
+        if self.symbol('@') {
+            match self.peek() {
+                Some(Token {
+                    kind: Kind::String(label),
+                    ..
+                }) => {
+                    expressions.push(Expression::Label(label.clone()));
+                    self.position += 1;
+                }
+                _ => return Err(self.error("a message after `@`")),
+            }
+        }
+
+        Ok(expressions)
+    }
+
+    // los predicados abarcan las repeticiones: `!a*` es `!(a*)`
+    fn element(&mut self) -> Result<Expression> {
+        for (symbol, negated) in [('&', false), ('!', true)] {
+            if self.symbol(symbol) {
+                return Ok(Expression::Predicate {
+                    expression: Box::new(self.element()?),
+                    negated,
+                });
+            }
+        }
+
+        let mut expression = self.primary()?;
+        loop {
+            let (min, max) = match self.peek() {
+                Some(Token {
+                    kind: Kind::Symbol('*'),
+                    ..
+                }) => (0, None),
+                Some(Token {
+                    kind: Kind::Symbol('+'),
+                    ..
+                }) => (1, None),
+                Some(Token {
+                    kind: Kind::Symbol('?'),
+                    ..
+                }) => (0, Some(1)),
+                _ => return Ok(expression),
+            };
+
+            self.position += 1;
+            expression = Expression::Repeat {
+                expression: Box::new(expression),
+                min,
+                max,
+            };
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression> {
+        // nota: si existe una regla con el nombre exacto de varios tokens pegados (por
+        // ejemplo una regla llamada `instruction*`) se usa la regla, como en las gramaticas
+        // anteriores a los operadores
+        if let Some(name) = self.defined_name() {
+            return Ok(Expression::named(name));
+        }
+
+        let Some(token) = self.peek() else {
+            return Err(self.error("an expression"));
+        };
+
+        let expression = match &token.kind {
+            Kind::String(text) => Expression::String(text.clone()),
+            Kind::Set { ranges, negated } => Expression::Set {
+                ranges: ranges.clone(),
+                negated: *negated,
+            },
+            Kind::Symbol('(') => {
+                self.position += 1;
+                let options = self.options()?;
+                self.expect(')', "`~`, `|` or `)`")?;
+                return Ok(Expression::Group(options));
+            }
+            Kind::Name if &self.text[token.span.0..token.span.1] == PRECEDENCE => {
+                self.position += 1;
+                return self.precedence();
+            }
+            Kind::Name => Expression::named(&self.text[token.span.0..token.span.1]),
+            _ => return Err(self.error("an expression")),
+        };
+
+        self.position += 1;
+        Ok(expression)
+    }
+
+    // el nombre de regla mas largo formado por dos o mas tokens pegados
+    fn defined_name(&mut self) -> Option<&str> {
+        let start = self.peek()?.span.0;
+        let mut last = self.position;
+        while self
+            .tokens
+            .get(last + 1)
+            .is_some_and(|token| token.span.0 == self.tokens[last].span.1)
+        {
+            last += 1;
+        }
+
+        let end = (self.position + 1..=last).rev().find(|end| {
+            self.rules
+                .contains(&self.text[start..self.tokens[*end].span.1])
+        })?;
+
+        self.position = end + 1;
+        Some(&self.text[start..self.tokens[end].span.1])
+    }
+
+    // `PRECEDENCE(operando ; left "+" | "-" ; ...)`: el operando va primero y cada nivel
+    // empieza por como se aplican sus operadores
+    fn precedence(&mut self) -> Result<Expression> {
+        self.expect('(', "`(` after PRECEDENCE")?;
+        let operand = self.element()?;
+
+        let mut levels = Vec::new();
+        while self.symbol(';') {
+            let fixity = match self
+                .peek()
+                .map(|token| &self.text[token.span.0..token.span.1])
+            {
+                Some("left") => Fixity::Left,
+                Some("right") => Fixity::Right,
+                Some("prefix") => Fixity::Prefix,
+                Some("postfix") => Fixity::Postfix,
+                _ => return Err(self.error("`left`, `right`, `prefix` or `postfix`")),
+            };
+            self.position += 1;
+            levels.push((fixity, self.options()?));
+        }
+
+        self.expect(')', "`;` or `)`")?;
+        Ok(Expression::Precedence {
+            operand: Box::new(operand),
+            levels,
+        })
+    }
+}
+
+// traduce las secuencias de escape de un string o un caracter de la gramatica:
+// `\n`, `\r`, `\t`, `\0`, `\\`, `\"`, `\'` y `\u{…}`
+fn unescape(text: &str) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        result.push(match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            c @ ('\\' | '"' | '\'') => c,
+            'u' => {
+                let rest = chars.as_str().strip_prefix('{')?;
+                let (hex, tail) = rest.split_once('}')?;
+                chars = tail.chars();
+                char::from_u32(u32::from_str_radix(hex, 16).ok()?)?
+            }
+            _ => return None,
+        });
+    }
+
+    Some(result)
+}
+
+// lee un conjunto de caracteres como `'a'..'z' '_'` (sin los corchetes)
+fn parse_set(text: &str) -> Option<Vec<(char, char)>> {
+    let mut ranges = Vec::new();
+    let mut rest = text.trim_start();
+
+    while !rest.is_empty() {
+        let (first, tail) = parse_char(rest)?;
+        let (last, tail) = match tail.trim_start().strip_prefix("..") {
+            Some(tail) => parse_char(tail.trim_start())?,
+            None => (first, tail),
+        };
+
+        if first > last {
+            return None;
+        }
+
+        ranges.push((first, last));
+        rest = tail.trim_start();
+    }
+
+    (!ranges.is_empty()).then_some(ranges)
+}
+
+// lee un caracter entre comillas simples y devuelve el texto que le sigue
+fn parse_char(text: &str) -> Option<(char, &str)> {
+    // nota: `quoted` salta el primer caracter sin mirarlo
+    let end = text.strip_prefix('\'').and_then(|_| quoted(text, '\''))?;
+    let value = unescape(&text[1..end - 1])?;
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some((c, &text[end..])),
+        _ => None,
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{analyzer::Analyzer, diagnostic::Report};
+use std::{collections::HashMap, path::Path};
+
+// Relaciona extensiones de archivo con la gramática que se usa para analizarlos,
+// de forma que en una misma ejecución se puedan resaltar varios lenguajes.
+pub struct Registry {
+    default: String,
+    languages: HashMap<String, Analyzer>,
+}
+
+impl Registry {
+    // el registro se crea con la gramática por defecto asociada a una extensión
+    pub fn new(extension: &str, analyzer: Analyzer) -> Self {
+        let mut languages = HashMap::new();
+        languages.insert(extension.to_string(), analyzer);
+
+        Registry {
+            default: extension.to_string(),
+            languages,
+        }
+    }
+
+    // registra (o reemplaza) la gramática de una extensión
+    pub fn register(&mut self, extension: &str, analyzer: Analyzer) {
+        self.languages.insert(extension.to_string(), analyzer);
+    }
+
+    // activa o desactiva la memoización en todas las gramáticas
+    pub fn set_memoize(&mut self, enabled: bool) {
+        for analyzer in self.languages.values_mut() {
+            analyzer.memoize = enabled;
+        }
+    }
+
+    // extensiones con gramática registrada
+    pub fn extensions(&self) -> impl Iterator<Item = &str> {
+        self.languages.keys().map(String::as_str)
+    }
+
+    // devuelve el analizador según la extensión del archivo
+    // nota: si la extensión no está registrada se usa la gramática por defecto
+    pub fn get(&self, path: &Path) -> &Analyzer {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.languages.get(ext))
+            .unwrap_or(&self.languages[&self.default])
+    }
+
+    // falla si alguna gramática tiene errores, con el informe de todas
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let report = self.report();
+        if report.errors > 0 {
+            return Err(anyhow::anyhow!(
+                "Invalid grammar:\n{}",
+                report.text.trim_end()
+            ));
+        }
+
+        Ok(())
+    }
+
+    // informe con los errores y las advertencias de todas las gramáticas, ordenadas por
+    // extensión
+    pub fn report(&self) -> Report {
+        let mut extensions: Vec<&String> = self.languages.keys().collect();
+        extensions.sort();
+
+        let mut report = Report::default();
+        for extension in extensions {
+            let analyzer = &self.languages[extension];
+            report.add(&analyzer.diagnostics(), &analyzer.name, &analyzer.source);
+        }
+
+        report
+    }
+}
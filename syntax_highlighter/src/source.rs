@@ -0,0 +1,88 @@
+// Normalización del texto antes de analizarlo.
+// El analizador trabaja con el texto normalizado, pero todo lo que se le muestra al usuario
+// (el código resaltado, las posiciones de los errores) se traduce al texto original
+// mediante un mapa de desplazamientos.
+
+// Sustituciones que se aplican al normalizar, en orden de prioridad.
+const REPLACEMENTS: [(&str, &str); 3] = [
+    // el BOM se trata como un espacio para que el analizador lo pueda omitir
+    ("\u{feff}", " "),
+    // los saltos de línea de Windows y los retornos de carro sueltos se unifican
+    ("\r\n", "\n"),
+    ("\r", "\n"),
+];
+
+pub struct Source<'a> {
+    pub original: &'a str,
+    pub text: String,
+    // pares (posición normalizada, posición original) a partir de los cuales cambia
+    // la diferencia entre ambos textos
+    map: Vec<(usize, usize)>,
+}
+
+impl<'a> Source<'a> {
+    pub fn new(original: &'a str) -> Self {
+        let mut text = String::with_capacity(original.len());
+        let mut map = vec![(0, 0)];
+        let mut rest = original;
+
+        'outer: while let Some(c) = rest.chars().next() {
+            for (from, to) in REPLACEMENTS {
+                if let Some(tail) = rest.strip_prefix(from) {
+                    text.push_str(to);
+                    rest = tail;
+
+                    // se registra dónde empieza el texto que sigue a la sustitución
+                    if from.len() != to.len() {
+                        map.push((text.len(), original.len() - rest.len()));
+                    }
+                    continue 'outer;
+                }
+            }
+
+            text.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+
+        Source {
+            original,
+            text,
+            map,
+        }
+    }
+
+    // traduce una posición del texto normalizado al texto original
+    pub fn original_offset(&self, offset: usize) -> usize {
+        let i = self
+            .map
+            .partition_point(|(normalized, _)| *normalized <= offset)
+            - 1;
+        let (normalized, original) = self.map[i];
+        let limit = self
+            .map
+            .get(i + 1)
+            .map_or(self.original.len(), |next| next.1);
+        (original + offset - normalized).min(limit)
+    }
+
+    // traduce una posición del texto original al texto normalizado
+    pub fn normalized_offset(&self, offset: usize) -> usize {
+        let i = self
+            .map
+            .partition_point(|(_, original)| *original <= offset)
+            - 1;
+        let (normalized, original) = self.map[i];
+        let limit = self.map.get(i + 1).map_or(self.text.len(), |next| next.0);
+        (normalized + offset - original).min(limit)
+    }
+
+    pub fn original_span(&self, (first, last): (usize, usize)) -> (usize, usize) {
+        (self.original_offset(first), self.original_offset(last))
+    }
+
+    // el texto original que corresponde a un rango del texto normalizado
+    pub fn original_slice(&self, span: (usize, usize)) -> &'a str {
+        let (first, last) = self.original_span(span);
+        &self.original[first..last]
+    }
+}
@@ -0,0 +1,230 @@
+// Tabla de reglas compilada: la gramatica se traduce una sola vez al crear el analizador
+// a una tabla indexada por numeros, de modo que durante el analisis no se buscan reglas
+// por nombre ni se comparan textos para saber de que tipo es cada expresion.
+use std::collections::HashMap;
+
+pub type RuleId = usize;
+
+// conjunto de caracteres de una regla interna
+// nota: los caracteres ASCII se consultan en un mapa de bits, el resto con la funcion
+#[derive(Debug, Clone, Copy)]
+pub struct CharClass {
+    ascii: u128,
+    matches: fn(char) -> bool,
+}
+
+impl CharClass {
+    pub fn new(matches: fn(char) -> bool) -> Self {
+        let ascii = (0..128u8)
+            .filter(|byte| matches(*byte as char))
+            .fold(0, |ascii, byte| ascii | 1 << byte);
+
+        CharClass { ascii, matches }
+    }
+
+    // cantidad de bytes al inicio del texto que pertenecen al conjunto
+    pub fn span(&self, text: &str) -> usize {
+        let bytes = text.as_bytes();
+        let mut end = 0;
+
+        while let Some(&byte) = bytes.get(end) {
+            if byte < 128 {
+                if self.ascii & 1 << byte == 0 {
+                    break;
+                }
+                end += 1;
+            } else {
+                match text[end..].chars().next() {
+                    Some(c) if (self.matches)(c) => end += c.len_utf8(),
+                    _ => break,
+                }
+            }
+        }
+
+        end
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        match c.is_ascii() {
+            true => self.ascii & 1 << c as u8 != 0,
+            false => (self.matches)(c),
+        }
+    }
+}
+
+// conjunto de caracteres escrito en la gramatica, como `['a'..'z' '_']`
+// nota: a diferencia de las reglas internas, reconoce un solo caracter
+#[derive(Debug)]
+pub struct CharSet {
+    ascii: u128,
+    // rangos que incluyen caracteres fuera de ASCII
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl CharSet {
+    pub fn new(ranges: Vec<(char, char)>, negated: bool) -> Self {
+        let ascii = (0..128u8)
+            .filter(|byte| {
+                let c = *byte as char;
+                ranges
+                    .iter()
+                    .any(|(first, last)| (*first..=*last).contains(&c))
+                    != negated
+            })
+            .fold(0, |ascii, byte| ascii | 1 << byte);
+        let ranges = ranges
+            .into_iter()
+            .filter(|(_, last)| !last.is_ascii())
+            .collect();
+
+        CharSet {
+            ascii,
+            ranges,
+            negated,
+        }
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        if c.is_ascii() {
+            return self.ascii & 1 << c as u8 != 0;
+        }
+
+        self.ranges
+            .iter()
+            .any(|(first, last)| (*first..=*last).contains(&c))
+            != self.negated
+    }
+}
+
+// expresion compilada
+#[derive(Debug)]
+pub enum Item {
+    // texto que se debe encontrar y como se describe si no se encuentra
+    String {
+        text: String,
+        expected: String,
+    },
+    // nota: `word` indica que la keyword debe terminar la palabra
+    Keyword {
+        text: String,
+        expected: String,
+        word: bool,
+    },
+    Rule(RuleId),
+    Set {
+        set: CharSet,
+        expected: String,
+    },
+    // nota: las reglas internas que no se reportan como esperadas (los espacios) no
+    // tienen descripcion
+    Class {
+        class: CharClass,
+        expected: Option<&'static str>,
+    },
+    Empty,
+    // la expresion se repite entre `min` y `max` veces
+    Repeat {
+        item: Box<Item>,
+        min: usize,
+        max: Option<usize>,
+    },
+    // se cumple si la expresion se cumple (o no, si se niega) sin avanzar
+    Predicate {
+        item: Box<Item>,
+        negated: bool,
+    },
+    // opciones dentro de una regla, se analizan como las opciones de una regla
+    Group(Vec<Sequence>),
+    Precedence(Box<Precedence>),
+}
+
+impl Item {
+    // la expresion que se repite, sin los operadores
+    pub fn base(&self) -> &Item {
+        match self {
+            Item::Repeat { item, .. } => item.base(),
+            _ => self,
+        }
+    }
+}
+
+// operandos unidos por operadores con distinta precedencia
+#[derive(Debug)]
+pub struct Precedence {
+    pub operand: Item,
+    // niveles de operadores binarios, del que une menos al que une mas
+    pub levels: Vec<Level>,
+    pub prefix: Vec<Sequence>,
+    pub postfix: Vec<Sequence>,
+}
+
+#[derive(Debug)]
+pub struct Level {
+    pub operators: Vec<Sequence>,
+    // si los operadores asocian por la derecha
+    pub right: bool,
+}
+
+// opcion de una regla o de un grupo
+#[derive(Debug)]
+pub struct Sequence {
+    pub items: Vec<Item>,
+    // mensaje con el que se reportan los errores de la opcion, si la gramatica define uno
+    pub label: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Rule {
+    pub name: String,
+    // nombre legible para los mensajes de error
+    pub description: String,
+    pub options: Vec<Sequence>,
+    // si la regla se recupera de sus errores
+    pub recover: bool,
+    // si dentro de la regla no se saltan los espacios ni los comentarios
+    pub atomic: bool,
+    // si la regla no aparece en el arbol: sus tokens quedan como hijos de la regla que la usa
+    pub silent: bool,
+    // si el token de la regla no tiene hijos
+    pub leaf: bool,
+    // si la regla se llama a si misma sin consumir texto
+    // nota: su resultado depende del resultado parcial del ciclo, por lo que no se memoiza
+    pub left_recursive: bool,
+    // si la regla se analiza haciendo crecer su resultado, una por cada ciclo
+    pub grows: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct RuleTable {
+    pub rules: Vec<Rule>,
+    pub initial: RuleId,
+    // reglas que se saltan entre las expresiones de las reglas que no son atomicas
+    pub trivia: Vec<RuleId>,
+    ids: HashMap<String, RuleId>,
+}
+
+impl RuleTable {
+    // devuelve el numero de la regla, agregandola sin opciones si todavia no existe
+    // nota: las reglas que no estan definidas quedan sin opciones y nunca se cumplen
+    pub fn insert(&mut self, name: &str, description: String) -> RuleId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+
+        let id = self.rules.len();
+        self.ids.insert(name.to_string(), id);
+        self.rules.push(Rule {
+            name: name.to_string(),
+            description,
+            options: Vec::new(),
+            recover: false,
+            atomic: false,
+            silent: false,
+            leaf: false,
+            left_recursive: false,
+            grows: false,
+        });
+        id
+    }
+}
@@ -1,30 +1,30 @@
-use crate::{analyzer::Analyzer, generate::create_mark};
+use crate::{generate::create_mark, registry::Registry};
 use rayon::prelude::*;
-use std::{collections::VecDeque, fs::DirEntry};
+use std::path::{Path, PathBuf};
 
-// Procesa cada archivo en la cola en paralelo.
-#[allow(dead_code)]
-pub fn parallel(files_queue: VecDeque<DirEntry>, analyzer: &Analyzer, template: &String, n: usize) {
-    println!("Procesando en paralelo...");
+// Procesa cada archivo en la cola en paralelo y devuelve los errores de los que fallan.
+// nota: un archivo que falla no detiene el procesamiento de los demás
+pub fn parallel(
+    files_queue: &[PathBuf],
+    registry: &Registry,
+    template: &str,
+    output: &Path,
+) -> Vec<anyhow::Error> {
     files_queue
-        .range(..n)
-        .collect::<Vec<_>>()
         .par_iter()
-        .for_each(|entry| create_mark(&analyzer, entry, template));
+        .filter_map(|path| create_mark(registry.get(path), path, template, output).err())
+        .collect()
 }
 
-// Procesa cada archivo en la cola de forma secuencial.
-#[allow(dead_code)]
+// Procesa cada archivo en la cola de forma secuencial y devuelve los errores de los que fallan.
 pub fn sequential(
-    files_queue: VecDeque<DirEntry>,
-    analyzer: &Analyzer,
-    template: &String,
-    n: usize,
-) {
-    println!("Procesando de forma secuencial...");
+    files_queue: &[PathBuf],
+    registry: &Registry,
+    template: &str,
+    output: &Path,
+) -> Vec<anyhow::Error> {
     files_queue
-        .range(..n)
-        .collect::<Vec<_>>()
         .iter()
-        .for_each(|entry| create_mark(&analyzer, entry, template));
+        .filter_map(|path| create_mark(registry.get(path), path, template, output).err())
+        .collect()
 }
@@ -0,0 +1,79 @@
+// Comprobaciones de `check` sobre el corpus y sobre entradas aleatorias: el HTML generado
+// debe estar bien formado y las hojas del árbol deben reproducir la entrada.
+mod common;
+
+use common::{analyzer, default_analyzer};
+use std::{fs, path::Path};
+use syntax_highlighter::{check, Analyzer};
+
+const RANDOM_INPUTS: u64 = 500;
+
+fn assert_checks(analyzer: &Analyzer, name: &str, input: &str) {
+    if let Err(e) = check::check_html(analyzer, input) {
+        panic!("{}: {}", name, e);
+    }
+    if let Err(e) = check::check_round_trip(analyzer, input) {
+        panic!("{}: {}", name, e);
+    }
+}
+
+// todos los archivos de una carpeta con la extensión indicada
+fn inputs(folder: &str, extension: &str) -> Vec<(String, String)> {
+    let folder = Path::new(env!("CARGO_MANIFEST_DIR")).join(folder);
+    let mut paths: Vec<_> = fs::read_dir(folder)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            (
+                path.display().to_string(),
+                fs::read_to_string(&path).unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn corpus() {
+    let analyzer = default_analyzer();
+    let inputs = inputs("corpus", "txt");
+    assert!(!inputs.is_empty());
+
+    for (name, input) in inputs {
+        assert_checks(&analyzer, &name, &input);
+    }
+}
+
+#[test]
+fn corpus_with_memoization() {
+    let mut analyzer = default_analyzer();
+    analyzer.memoize = true;
+
+    for (name, input) in inputs("corpus", "txt") {
+        assert_checks(&analyzer, &name, &input);
+    }
+}
+
+#[test]
+fn bench_inputs() {
+    let analyzer = analyzer("bench/arithmetic.grammar", "program");
+
+    for (name, input) in inputs("bench", "calc") {
+        assert_checks(&analyzer, &name, &input);
+    }
+}
+
+#[test]
+fn random_inputs() {
+    let analyzer = default_analyzer();
+
+    for seed in 0..RANDOM_INPUTS {
+        let input = check::random_input(seed, 64);
+        assert_checks(&analyzer, &format!("seed {}", seed), &input);
+    }
+}
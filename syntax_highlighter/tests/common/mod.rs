@@ -0,0 +1,42 @@
+// Funciones compartidas por las pruebas de integración.
+// nota: cada prueba usa solo algunas
+#![allow(dead_code)]
+use std::path::Path;
+use syntax_highlighter::{Analyzer, Token};
+
+// analizador de una gramática del repositorio, con la ruta relativa a la raíz del paquete
+pub fn analyzer(grammar: &str, rule: &str) -> Analyzer {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(grammar);
+    Analyzer::from_file(path, rule).unwrap()
+}
+
+// analizador de la gramática por defecto
+pub fn default_analyzer() -> Analyzer {
+    analyzer("src/grammar.txt", "program")
+}
+
+// árbol de reglas con el texto de las hojas, como `(expression (expression 1) - 2)`
+// nota: se omiten las hojas que son solo espacios
+pub fn shape(token: &Token, input: &str) -> String {
+    let Token(rule, ((start, end), children)) = token;
+    match children {
+        Some(children) => {
+            let children: Vec<String> = children
+                .iter()
+                .filter(|child| !input[child.1 .0 .0..child.1 .0 .1].trim().is_empty())
+                .map(|child| shape(child, input))
+                .collect();
+            format!("({} {})", rule, children.join(" "))
+        }
+        None => input[*start..*end].to_string(),
+    }
+}
+
+// reglas y texto de todos los tokens del árbol, en orden
+pub fn tokens<'a>(token: &Token<'a>, input: &'a str, found: &mut Vec<(&'a str, &'a str)>) {
+    let Token(rule, ((start, end), children)) = token;
+    found.push((rule, &input[*start..*end]));
+    for child in children.iter().flat_map(|children| children.iter()) {
+        tokens(child, input, found);
+    }
+}
@@ -0,0 +1,169 @@
+// Lectura y carga de gramáticas: el formato admite comentarios y reglas de varias líneas,
+// y los problemas de todas las reglas se reportan juntos, cada uno con su línea.
+use syntax_highlighter::{Analyzer, Registry};
+
+// informe de una gramática que no se puede cargar
+fn report(grammar: &str) -> String {
+    match Analyzer::new(grammar, "program") {
+        Ok(_) => panic!("the grammar should be invalid"),
+        Err(error) => error.to_string(),
+    }
+}
+
+#[test]
+fn read_errors_are_reported_with_validation() {
+    let report = report("program = foo ~ bar\nbar = \"x\" |\nunused = \"u\"\n");
+    assert!(report.contains("error: Invalid rule: foo\n --> grammar:1:11"));
+    assert!(report.contains("error: Expected an expression, found end of rule\n --> grammar:2:12"));
+    assert!(report.contains("warning: Unused rule: unused\n --> grammar:3:1"));
+    // la regla que no se pudo leer no se reporta como no definida
+    assert!(!report.contains("Invalid rule: bar"));
+}
+
+#[test]
+fn duplicate_rule_points_at_both_definitions() {
+    let report = report("program = a\na = \"x\"\na = \"y\"\n");
+    assert!(report.contains(
+        "error: Duplicate rule: a\n --> grammar:3:1\n  |\n3 | a = \"y\"\n  | ^\n\n\
+         note: first defined here\n --> grammar:2:1\n  |\n2 | a = \"x\"\n  | ^"
+    ));
+}
+
+// errores de una gramática, sin la línea ni el rango marcado
+fn errors(grammar: &str) -> Vec<String> {
+    report(grammar)
+        .lines()
+        .filter_map(|line| line.strip_prefix("error: "))
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn comments_and_rules_over_several_lines() {
+    let grammar = r#"
+// reglas de prueba
+program = "a" // primera opción
+    | "b" ~ "c"
+    | "d"
+"#;
+    let analyzer = Analyzer::new(grammar, "program").unwrap();
+    assert!(analyzer.diagnostics().is_empty());
+    for input in ["a", "bc", "d"] {
+        assert!(analyzer.parse(input).errors.is_empty(), "{}", input);
+    }
+}
+
+#[test]
+fn symbols_inside_strings_and_sets() {
+    let grammar = r#"program = "|" ~ "~" ~ "//" ~ ['|' '~' ']']"#;
+    let analyzer = Analyzer::new(grammar, "program").unwrap();
+    assert!(analyzer.parse("|~//]").errors.is_empty());
+    assert!(analyzer.parse("|~//~").errors.is_empty());
+}
+
+#[test]
+fn equal_sign_without_name() {
+    assert_eq!(
+        errors("program = ="),
+        [
+            "Expected a rule name before `=`",
+            "Expected an expression, found `=`"
+        ]
+    );
+    assert_eq!(
+        errors("program = = \"x\""),
+        [
+            "Expected a rule name before `=`",
+            "Expected an expression, found `=`"
+        ]
+    );
+}
+
+#[test]
+fn set_characters_must_be_quoted() {
+    assert_eq!(errors("program = [é'x']"), ["Invalid character set"]);
+    assert_eq!(errors("program = [ab'x']"), ["Invalid character set"]);
+}
+
+// si cada entrada se reconoce completa con la gramática
+fn matches(grammar: &str, inputs: &[&str]) -> Vec<bool> {
+    let analyzer = Analyzer::new(grammar, "program").unwrap();
+    inputs
+        .iter()
+        .map(|input| analyzer.parse(input).errors.is_empty())
+        .collect()
+}
+
+#[test]
+fn escape_sequences() {
+    assert_eq!(
+        matches(
+            r#"program = "\u{48}\u{e9}\n\t\\\"\0""#,
+            &["Hé\n\t\\\"\0", "H"]
+        ),
+        [true, false]
+    );
+    assert_eq!(
+        matches(r"program = ['\u{3b1}'..'\u{3c9}']", &["β", "b"]),
+        [true, false]
+    );
+    for string in [
+        r"\u{D800}",
+        r"\u{110000}",
+        r"\u{}",
+        r"\u{zz}",
+        r"\u41",
+        r"\q",
+    ] {
+        assert_eq!(
+            errors(&format!("program = \"{}\"", string)),
+            ["Invalid escape sequence in string"],
+            "{}",
+            string
+        );
+    }
+    assert_eq!(errors(r"program = ['\u{D800}']"), ["Invalid character set"]);
+}
+
+#[test]
+fn negated_sets() {
+    assert_eq!(
+        matches("program = [^'a'..'z' '_']+", &["AZ9é", "Ab", "_"]),
+        [true, false, false]
+    );
+}
+
+#[test]
+fn sets_with_non_ascii_ranges() {
+    assert_eq!(
+        matches(
+            "program = ['a'..'c' 'é' 'α'..'ω']+",
+            &["abé", "βγ", "d", "ü", "б"]
+        ),
+        [true, true, false, false, false]
+    );
+    assert_eq!(
+        matches("program = [^'a'..'c' 'α'..'ω']+", &["dü", "б", "b", "β"]),
+        [true, true, false, false]
+    );
+}
+
+#[test]
+fn invalid_strings_and_sets() {
+    assert_eq!(errors("program = ['z'..'a']"), ["Invalid character set"]);
+    assert_eq!(errors("program = []"), ["Invalid character set"]);
+    assert_eq!(errors("program = ['ab']"), ["Invalid character set"]);
+    assert_eq!(errors("program = \"abc\n"), ["Unterminated string"]);
+    assert_eq!(errors("program = ['a'"), ["Unterminated character set"]);
+}
+
+#[test]
+fn registry_reports_every_invalid_grammar() {
+    let first = Analyzer::new("program = foo", "program").unwrap();
+    let second = Analyzer::new("program = bar", "program").unwrap();
+    let mut registry = Registry::new("a", first);
+    registry.register("b", second);
+    let report = registry.validate().unwrap_err().to_string();
+    assert!(report.contains("Invalid rule: foo"));
+    assert!(report.contains("Invalid rule: bar"));
+}
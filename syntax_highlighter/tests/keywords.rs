@@ -0,0 +1,66 @@
+// Keywords y predicados: una keyword solo se cumple con la palabra completa, y `&` y `!`
+// comprueban lo que sigue sin dejar tokens ni errores en el resultado.
+mod common;
+
+use common::{default_analyzer, shape, tokens};
+use std::{fs, path::Path};
+use syntax_highlighter::Analyzer;
+
+// texto de los tokens de una regla
+fn texts<'a>(found: &[(&str, &'a str)], rule: &str) -> Vec<&'a str> {
+    found
+        .iter()
+        .filter(|(name, _)| *name == rule)
+        .map(|(_, text)| *text)
+        .collect()
+}
+
+#[test]
+fn keywords_match_whole_words() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus/keywords.txt");
+    let input = fs::read_to_string(path).unwrap();
+    let analyzer = default_analyzer();
+    let parsed = analyzer.parse(&input);
+    let mut found = Vec::new();
+    tokens(&parsed.token, &input, &mut found);
+
+    assert_eq!(
+        texts(&found, "ident"),
+        ["variable", "iffy", "iffy", "whilex", "functional", "a", "a"]
+    );
+    assert_eq!(
+        texts(&found, "keyword"),
+        ["var", "if", "function", "return"]
+    );
+    // `!reserved` no deja tokens de las keywords que prueba dentro de `ident`
+    assert!(found
+        .iter()
+        .all(|(rule, _)| *rule != "reserved" && !rule.ends_with("_keyword")));
+
+    let errors: Vec<(&str, usize)> = parsed
+        .errors
+        .iter()
+        .map(|error| (error.message.as_str(), error.position))
+        .collect();
+    assert_eq!(errors, [("expected ident in program, found `if`", 64)]);
+}
+
+#[test]
+fn predicates_leave_no_tokens_or_errors() {
+    let grammar = "program = &\"a\" ~ word ~ !\"!\"\nword = @{ ASCII_ALPHA+ }\n";
+    let analyzer = Analyzer::new(grammar, "program").unwrap();
+
+    let parsed = analyzer.parse("abc");
+    assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+    assert_eq!(shape(&parsed.token, "abc"), "(root (program abc))");
+    let mut found = Vec::new();
+    tokens(&parsed.token, "abc", &mut found);
+    assert_eq!(
+        found,
+        [("root", "abc"), ("program", "abc"), ("word", "abc")]
+    );
+
+    for input in ["bc", "abc!"] {
+        assert!(!analyzer.parse(input).errors.is_empty(), "{}", input);
+    }
+}
@@ -0,0 +1,49 @@
+// Mensajes de la gramática: `@ "mensaje"` reemplaza el mensaje generado solo cuando falla
+// la última expresión de la opción, o la regla o grupo que lo tiene.
+use syntax_highlighter::Analyzer;
+
+const GRAMMAR: &str = r#"
+WHITESPACE = WHITESPACE
+program = statement*
+statement = "var" ~ ident ~ "=" ~ value ~ ";" @ "missing semicolon after declaration"
+value = (number | ident) @ "expected a value"
+ident = @{ ASCII_ALPHA+ }
+number = @{ ASCII_DIGIT+ }
+"#;
+
+// mensajes y posiciones de los errores del analisis
+fn errors(input: &str) -> Vec<(String, usize)> {
+    let analyzer = Analyzer::new(GRAMMAR, "program").unwrap();
+    let parsed = analyzer.parse(input);
+    parsed
+        .errors
+        .iter()
+        .map(|error| (error.message.clone(), error.position))
+        .collect()
+}
+
+#[test]
+fn label_of_last_element() {
+    assert_eq!(
+        errors("var x = 1"),
+        [("missing semicolon after declaration".to_string(), 9)]
+    );
+}
+
+#[test]
+fn label_of_group_that_fails() {
+    assert_eq!(errors("var x = ;"), [("expected a value".to_string(), 8)]);
+}
+
+#[test]
+fn earlier_failures_keep_generated_message() {
+    assert_eq!(
+        errors("var x 1;"),
+        [("expected `=` after ident, found `1`".to_string(), 6)]
+    );
+}
+
+#[test]
+fn failure_is_reported_once() {
+    assert_eq!(errors("var x = "), [("expected a value".to_string(), 8)]);
+}
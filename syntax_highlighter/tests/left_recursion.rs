@@ -0,0 +1,62 @@
+// Recursión por la izquierda: los ciclos crecen desde una opción que no vuelve al ciclo, y
+// la revisión de la gramática reporta los ciclos que no tienen ninguna.
+mod common;
+
+use common::shape;
+use syntax_highlighter::{Analyzer, Severity};
+
+const GRAMMAR: &str = r#"
+WHITESPACE = WHITESPACE
+expression = expression ~ "-" ~ term | term
+term = @{ ASCII_DIGIT+ }
+"#;
+
+// gravedad y mensaje de lo que reporta la revisión de la gramática sobre los ciclos
+fn diagnostics(grammar: &str) -> Vec<(Severity, String)> {
+    let analyzer = Analyzer::new(grammar, "program").unwrap();
+    analyzer
+        .diagnostics()
+        .into_iter()
+        .filter(|diagnostic| diagnostic.message.starts_with("Left recursion"))
+        .map(|diagnostic| (diagnostic.severity, diagnostic.message))
+        .collect()
+}
+
+#[test]
+fn grows_to_the_left() {
+    let analyzer = Analyzer::new(GRAMMAR, "expression").unwrap();
+    let input = "1 - 2 - 3";
+    let parsed = analyzer.parse(input);
+    assert!(parsed.errors.is_empty());
+    assert_eq!(
+        shape(&parsed.token, input),
+        "(root (expression (expression (expression 1) - 2) - 3))"
+    );
+}
+
+#[test]
+fn cycle_without_base_is_an_error() {
+    assert_eq!(
+        diagnostics("program = a\na = a\n"),
+        [(
+            Severity::Error,
+            "Left recursion without a non-recursive option: a -> a".to_string()
+        )]
+    );
+    assert_eq!(
+        diagnostics("program = a\na = b ~ \"z\"\nb = a ~ \"y\"\n"),
+        [(
+            Severity::Error,
+            "Left recursion without a non-recursive option: a -> b -> a".to_string()
+        )]
+    );
+}
+
+#[test]
+fn cycle_with_base_is_not_reported() {
+    assert_eq!(
+        diagnostics("program = a\na = b | \"x\"\nb = a ~ \"y\"\n"),
+        []
+    );
+    assert_eq!(diagnostics("program = a\na = (a ~ \"y\" | \"x\")\n"), []);
+}
@@ -0,0 +1,54 @@
+// Conversión de posiciones en bytes a línea y columna.
+use syntax_highlighter::{LineCol, LineIndex};
+
+#[test]
+fn counts_every_kind_of_line_ending() {
+    let index = LineIndex::new("ab\r\ncd\ré\nf");
+    assert_eq!(index.line_count(), 4);
+
+    assert_eq!(index.line(0), 1);
+    assert_eq!(index.line(3), 1);
+    assert_eq!(index.line(4), 2);
+    assert_eq!(index.line(7), 3);
+    assert_eq!(index.line(10), 4);
+}
+
+#[test]
+fn reports_columns_in_bytes_and_utf16() {
+    let index = LineIndex::new("ab\r\ncd\ré\nf");
+    assert_eq!(
+        index.line_col(5),
+        LineCol {
+            line: 2,
+            column: 2,
+            column_utf16: 2
+        }
+    );
+
+    // `👍` ocupa 4 bytes y 2 unidades de UTF-16
+    let index = LineIndex::new("👍x");
+    assert_eq!(
+        index.line_col(4),
+        LineCol {
+            line: 1,
+            column: 5,
+            column_utf16: 3
+        }
+    );
+}
+
+#[test]
+fn clamps_positions_inside_characters_and_past_the_end() {
+    let index = LineIndex::new("👍x");
+
+    // dentro de un caracter se cuenta desde su inicio
+    assert_eq!(index.line_col(2).column_utf16, 1);
+    assert_eq!(index.line_col(100), index.line_col(5));
+}
+
+#[test]
+fn empty_text_has_one_line() {
+    let index = LineIndex::new("");
+    assert_eq!(index.line_count(), 1);
+    assert_eq!(index.line_col(0).line, 1);
+}
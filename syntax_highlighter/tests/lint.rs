@@ -0,0 +1,119 @@
+// Revisión de las opciones de la gramática: advertencias por opciones que nunca se prueban
+// o que quedan ocultas por una anterior.
+mod common;
+
+use common::default_analyzer;
+use std::time::{Duration, Instant};
+use syntax_highlighter::Analyzer;
+
+const RULES: &str = r#"
+number = @{ ASCII_DIGIT+ }
+float = ${ number ~ "." ~ number }
+"#;
+
+// advertencias de una gramática con `value` como regla inicial
+fn warnings(rule: &str) -> Vec<String> {
+    let grammar = format!("value = {}\n{}", rule, RULES);
+    let analyzer = Analyzer::new(&grammar, "value").unwrap();
+    analyzer
+        .diagnostics()
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect()
+}
+
+#[test]
+fn shadowed_through_rules() {
+    assert_eq!(
+        warnings("number | float"),
+        ["Shadowed alternative 2 in value: alternative 1 matches a prefix of it"]
+    );
+}
+
+#[test]
+fn shadowed_string_prefix() {
+    assert_eq!(
+        warnings(r#""=" | "==" | number | float"#),
+        [
+            "Shadowed alternative 2 in value: alternative 1 matches a prefix of it",
+            "Shadowed alternative 4 in value: alternative 3 matches a prefix of it"
+        ]
+    );
+}
+
+#[test]
+fn longer_alternative_first_is_not_ambiguous() {
+    assert_eq!(warnings("float | number"), Vec::<String>::new());
+    assert_eq!(
+        warnings(r#"number ~ "+" | number ~ "-" | float | number"#),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn overlapping_first_characters() {
+    assert_eq!(
+        warnings("(ASCII_DIGIT | number) ~ float"),
+        ["Ambiguous alternatives 1 and 2 in value: both can start with '0'"]
+    );
+}
+
+#[test]
+fn indirect_left_recursion_is_not_ambiguous() {
+    let rules = "a | float\na = b ~ \"x\" | \"y\"\nb = a ~ \"z\" | \"w\"";
+    assert_eq!(warnings(rules), Vec::<String>::new());
+}
+
+#[test]
+fn overlapping_sets() {
+    assert_eq!(
+        warnings("(['a'..'z'] | [^'0'..'9']) ~ float"),
+        ["Ambiguous alternatives 1 and 2 in value: both can start with 'a'"]
+    );
+    assert_eq!(
+        warnings("([^'\\0'..'z'] | ALPHA) ~ float"),
+        ["Ambiguous alternatives 1 and 2 in value: both can start with 'ª'"]
+    );
+    assert_eq!(warnings("(['a'] | [^'a']) ~ float"), Vec::<String>::new());
+}
+
+#[test]
+fn many_disjoint_sets() {
+    let sets: Vec<String> = ('a'..='z')
+        .chain('A'..='E')
+        .map(|c| format!("['{}']", c))
+        .collect();
+    let start = Instant::now();
+    assert_eq!(
+        warnings(&format!("({}) ~ float", sets.join(" | "))),
+        Vec::<String>::new()
+    );
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn unreachable_alternative() {
+    assert_eq!(
+        warnings(r#"(EMPTY | "x") ~ number ~ float"#),
+        ["Unreachable alternative 2 in value: alternative 1 always matches"]
+    );
+}
+
+#[test]
+fn nullable_repetition() {
+    assert_eq!(
+        warnings(r#"("a"?)* ~ number ~ float"#),
+        [r#"Nullable expression under repetition in value: ("a"?)*"#]
+    );
+}
+
+#[test]
+fn shipped_grammar_has_no_warnings() {
+    let analyzer = default_analyzer();
+    let diagnostics: Vec<String> = analyzer
+        .diagnostics()
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect();
+    assert_eq!(diagnostics, Vec::<String>::new());
+}
@@ -0,0 +1,53 @@
+// Modificadores de reglas: `_{}` deja los tokens de la regla en la que la usa, `@{}` deja
+// una sola hoja y `${}` conserva las reglas que usa, sin saltar espacios en ninguna de las dos.
+mod common;
+
+use common::{shape, tokens};
+use syntax_highlighter::Analyzer;
+
+const GRAMMAR: &str = r#"
+WHITESPACE = WHITESPACE
+program = silent ~ atomic ~ compound
+silent = _{ digit ~ digit }
+atomic = @{ digit ~ "." ~ digit }
+compound = ${ digit ~ "." ~ digit }
+digit = ASCII_DIGIT
+"#;
+
+// forma del árbol de la entrada y posiciones de los errores
+fn parse(input: &str) -> (String, Vec<usize>) {
+    let analyzer = Analyzer::new(GRAMMAR, "program").unwrap();
+    let parsed = analyzer.parse(input);
+    let errors = parsed.errors.iter().map(|error| error.position).collect();
+    (shape(&parsed.token, input), errors)
+}
+
+#[test]
+fn tree_shape_of_each_modifier() {
+    assert_eq!(
+        parse("1 2 3.4 5.6"),
+        (
+            "(root (program (digit 1) (digit 2) 3.4 (compound (digit 5) . (digit 6))))".to_string(),
+            vec![]
+        )
+    );
+}
+
+#[test]
+fn atomic_rules_leave_one_leaf() {
+    let analyzer = Analyzer::new(GRAMMAR, "program").unwrap();
+    let input = "1 2 3.4 5.6";
+    let parsed = analyzer.parse(input);
+    let mut found = Vec::new();
+    tokens(&parsed.token, input, &mut found);
+    assert!(found.iter().all(|(rule, _)| *rule != "silent"));
+    // la hoja de `atomic` va seguida del espacio, sin sus `digit`
+    let atomic = found.iter().position(|token| *token == ("atomic", "3.4"));
+    assert_eq!(found[atomic.unwrap() + 1], ("WHITESPACE", " "));
+}
+
+#[test]
+fn atomic_rules_do_not_skip_spaces() {
+    assert_eq!(parse("1 2 3 .4 5.6").1.first(), Some(&5));
+    assert_eq!(parse("1 2 3.4 5 .6").1.first(), Some(&9));
+}
@@ -0,0 +1,73 @@
+// Expresiones con operadores: la precedencia y la asociatividad de cada nivel deciden la
+// forma del árbol, y un operador sin operando derecho se reporta como error.
+mod common;
+
+use common::{default_analyzer, shape};
+use syntax_highlighter::Analyzer;
+
+const GRAMMAR: &str = r#"
+WHITESPACE = WHITESPACE
+arithmetic = PRECEDENCE(number ; left "+" | "-" ; left "*" ; right "^" ; prefix "-" ; postfix "!")
+number = @{ ASCII_DIGIT+ }
+"#;
+
+// forma del árbol de una expresión, sin el nodo raíz
+fn parse(input: &str) -> String {
+    let analyzer = Analyzer::new(GRAMMAR, "arithmetic").unwrap();
+    let parsed = analyzer.parse(input);
+    assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+    let tree = shape(&parsed.token, input);
+    tree["(root ".len()..tree.len() - 1].to_string()
+}
+
+#[test]
+fn higher_levels_bind_tighter() {
+    assert_eq!(
+        parse("5 + 4 - 1 * 2"),
+        "(arithmetic (arithmetic 5 + 4) - (arithmetic 1 * 2))"
+    );
+}
+
+#[test]
+fn right_associative_level() {
+    assert_eq!(parse("2 ^ 3 ^ 4"), "(arithmetic 2 ^ (arithmetic 3 ^ 4))");
+}
+
+#[test]
+fn prefix_and_postfix_operators() {
+    assert_eq!(parse("-3!"), "(arithmetic - (arithmetic 3 !))");
+    assert_eq!(parse("- -3"), "(arithmetic - (arithmetic - 3))");
+}
+
+#[test]
+fn missing_right_operand_is_reported() {
+    let analyzer = default_analyzer();
+    let parsed = analyzer.parse("var x = 1 + ;\nvar y = 2;\n");
+    let errors: Vec<(&str, usize)> = parsed
+        .errors
+        .iter()
+        .map(|error| (error.message.as_str(), error.position))
+        .collect();
+    assert_eq!(
+        errors,
+        [("expected operand after expression, found `;`", 12)]
+    );
+}
+
+#[test]
+fn leftover_text_is_reported_without_recovery() {
+    let analyzer = Analyzer::new(GRAMMAR, "arithmetic").unwrap();
+    let parsed = analyzer.parse("1 + 2 +");
+    let errors: Vec<(&str, usize)> = parsed
+        .errors
+        .iter()
+        .map(|error| (error.message.as_str(), error.position))
+        .collect();
+    assert_eq!(
+        errors,
+        [(
+            "expected `-` or number in arithmetic, found end of input",
+            7
+        )]
+    );
+}
@@ -0,0 +1,97 @@
+// Recuperación de errores con la gramática por defecto: cada error de escritura debe
+// producir un solo mensaje y el análisis debe continuar después del punto de sincronización.
+mod common;
+
+use common::default_analyzer;
+use syntax_highlighter::{Analyzer, Token};
+
+// posiciones de los errores del analisis
+fn errors(input: &str) -> Vec<usize> {
+    let analyzer = default_analyzer();
+    let parsed = analyzer.parse(input);
+    parsed.errors.iter().map(|error| error.position).collect()
+}
+
+#[test]
+fn valid_program_has_no_errors() {
+    assert_eq!(errors("var x = 1;\nvar y = x + 2;\n"), []);
+}
+
+#[test]
+fn one_typo_gives_one_error() {
+    assert_eq!(errors("var x = 1 +* 2;\nvar y = 2;\n"), [11]);
+}
+
+#[test]
+fn stray_closing_brace_is_reported_once() {
+    assert_eq!(errors("var y = 2 }\nvar z = 3;\n"), [10]);
+    assert_eq!(errors("var y = 2\n}\n"), [10]);
+}
+
+#[test]
+fn each_stray_token_is_reported() {
+    assert_eq!(errors("var y = 2 }}\n"), [10, 11]);
+}
+
+// texto que quedo en nodos `error`, saltado por la recuperacion
+fn skipped(input: &str) -> Vec<String> {
+    fn visit(token: &Token, input: &str, skipped: &mut Vec<String>) {
+        let Token(rule, ((start, end), children)) = token;
+        if *rule == "error" {
+            skipped.push(input[*start..*end].to_string());
+        }
+        for child in children.iter().flat_map(|children| children.iter()) {
+            visit(child, input, skipped);
+        }
+    }
+
+    let analyzer = default_analyzer();
+    let mut skipped = Vec::new();
+    visit(&analyzer.parse(input).token, input, &mut skipped);
+    skipped
+}
+
+#[test]
+fn statement_after_block_is_not_skipped() {
+    let input = "if (a) { b = 1 }\nvar c = 2;\n";
+    assert_eq!(errors(input), [15]);
+    assert_eq!(skipped(input), Vec::<String>::new());
+}
+
+#[test]
+fn missing_rule_is_reported() {
+    assert_eq!(errors("var x = ;\n"), [8]);
+    assert_eq!(errors("x = ;\n"), [4]);
+    assert_eq!(errors("var = 1;\n"), [4]);
+    assert_eq!(errors("while (a) { b = ; }\n"), [16]);
+    assert_eq!(errors("x < 3;\n"), [2]);
+}
+
+#[test]
+fn alternatives_are_tried_after_a_rule_fails() {
+    assert_eq!(errors("var y = x < 3;\nf(x);\nvar z = f(1, 2);\n"), []);
+}
+
+#[test]
+fn leftover_text_is_reported_once_without_recovery() {
+    let grammar = r#"
+WHITESPACE = WHITESPACE
+program = digit ~ digit ~ number
+number = @{ digit ~ "." ~ digit }
+digit = ASCII_DIGIT
+"#;
+    let analyzer = Analyzer::new(grammar, "program").unwrap();
+    let parsed = analyzer.parse("1 2 3 .4");
+    let errors: Vec<(&str, usize)> = parsed
+        .errors
+        .iter()
+        .map(|error| (error.message.as_str(), error.position))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            ("expected `.` after digit, found whitespace", 5),
+            ("expected digit in program, found `.`", 6)
+        ]
+    );
+}
@@ -0,0 +1,55 @@
+// Operadores de repetición: cada repetición queda como un hijo más de la regla, `?` se
+// cumple a lo sumo una vez y una regla cuyo nombre termina en el operador tiene prioridad.
+mod common;
+
+use common::shape;
+use syntax_highlighter::Analyzer;
+
+const GRAMMAR: &str = r#"
+WHITESPACE = WHITESPACE
+list = item* ~ ";" ~ item+ ~ "."? ~ "!"
+item = "x"
+"#;
+
+// forma del árbol de la entrada y posiciones de los errores
+fn parse(grammar: &str, rule: &str, input: &str) -> (String, Vec<usize>) {
+    let analyzer = Analyzer::new(grammar, rule).unwrap();
+    let parsed = analyzer.parse(input);
+    let errors = parsed.errors.iter().map(|error| error.position).collect();
+    (shape(&parsed.token, input), errors)
+}
+
+#[test]
+fn repetitions_are_flat_children() {
+    assert_eq!(
+        parse(GRAMMAR, "list", "x x ; x x x . !"),
+        (
+            "(root (list (item x) (item x) ; (item x) (item x) (item x) . !))".to_string(),
+            vec![]
+        )
+    );
+    assert_eq!(
+        parse(GRAMMAR, "list", "; x !"),
+        ("(root (list ; (item x) !))".to_string(), vec![])
+    );
+}
+
+#[test]
+fn plus_needs_one_repetition() {
+    assert_eq!(parse(GRAMMAR, "list", "x ; !").1, [4]);
+}
+
+#[test]
+fn optional_matches_at_most_once() {
+    assert_eq!(parse(GRAMMAR, "list", "; x ..!").1, [5]);
+}
+
+#[test]
+fn rule_named_with_operator_is_used() {
+    let grammar = "program = instruction*\ninstruction* = \"a\" ~ \"b\"\ninstruction = \"x\"\n";
+    assert_eq!(
+        parse(grammar, "program", "ab"),
+        ("(root (program (instruction* a b)))".to_string(), vec![])
+    );
+    assert_eq!(parse(grammar, "program", "xx").1, [0]);
+}
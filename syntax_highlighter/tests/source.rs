@@ -0,0 +1,43 @@
+// Normalización de las entradas y mapa de posiciones hacia el texto original.
+use syntax_highlighter::Source;
+
+// `a\r\nb<BOM>c\rd`: los saltos de línea y el BOM cambian de largo al normalizar
+const ORIGINAL: &str = "a\r\nb\u{feff}c\rd";
+
+#[test]
+fn normalizes_line_endings_and_bom() {
+    let source = Source::new(ORIGINAL);
+    assert_eq!(source.text, "a\nb c\nd");
+    assert_eq!(source.original, ORIGINAL);
+}
+
+#[test]
+fn maps_normalized_offsets_to_the_original() {
+    let source = Source::new(ORIGINAL);
+    let offsets: Vec<usize> = (0..=source.text.len())
+        .map(|offset| source.original_offset(offset))
+        .collect();
+    assert_eq!(offsets, [0, 1, 3, 4, 7, 8, 9, 10]);
+
+    assert_eq!(source.original_slice((1, 2)), "\r\n");
+    assert_eq!(source.original_slice((3, 4)), "\u{feff}");
+    assert_eq!(source.original_slice((0, source.text.len())), ORIGINAL);
+}
+
+#[test]
+fn maps_original_offsets_to_the_normalized_text() {
+    let source = Source::new(ORIGINAL);
+    for (normalized, original) in [(0, 0), (2, 3), (3, 4), (4, 7), (6, 9), (7, 10)] {
+        assert_eq!(source.normalized_offset(original), normalized);
+    }
+
+    // una posición dentro de una sustitución se lleva al final de su reemplazo
+    assert_eq!(source.normalized_offset(2), 2);
+}
+
+#[test]
+fn leaves_normalized_text_unchanged() {
+    let source = Source::new("var a = 1;\n");
+    assert_eq!(source.text, source.original);
+    assert_eq!(source.original_span((4, 9)), (4, 9));
+}
@@ -0,0 +1,75 @@
+// Espacios y comentarios: si la gramática define `WHITESPACE` o `COMMENT` se saltan entre
+// los elementos de las secuencias y de las repeticiones, salvo dentro de reglas atómicas.
+mod common;
+
+use common::{shape, tokens};
+use syntax_highlighter::Analyzer;
+
+const GRAMMAR: &str = r#"
+WHITESPACE = WHITESPACE
+COMMENT = "//" ~ [^'\n']*
+list = item ~ ("," ~ item)*
+item = @{ ASCII_ALPHA+ ~ ("-" ~ ASCII_ALPHA+)? }
+"#;
+
+// posiciones de los errores del análisis
+fn errors(grammar: &str, input: &str) -> Vec<usize> {
+    let analyzer = Analyzer::new(grammar, "list").unwrap();
+    let parsed = analyzer.parse(input);
+    parsed.errors.iter().map(|error| error.position).collect()
+}
+
+#[test]
+fn spaces_and_comments_are_skipped_as_leaves() {
+    let analyzer = Analyzer::new(GRAMMAR, "list").unwrap();
+    let input = "a , // primero\n b,c";
+    let parsed = analyzer.parse(input);
+    assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+    assert_eq!(
+        shape(&parsed.token, input),
+        "(root (list a , // primero b , c))"
+    );
+
+    let mut found = Vec::new();
+    tokens(&parsed.token, input, &mut found);
+    assert_eq!(
+        found[2..],
+        [
+            ("item", "a"),
+            ("WHITESPACE", " "),
+            ("string", ","),
+            ("WHITESPACE", " "),
+            ("COMMENT", "// primero"),
+            ("WHITESPACE", "\n "),
+            ("item", "b"),
+            ("string", ","),
+            ("item", "c")
+        ]
+    );
+}
+
+#[test]
+fn nothing_is_skipped_inside_atomic_rules() {
+    assert_eq!(errors(GRAMMAR, "a-b, c"), []);
+    assert_eq!(errors(GRAMMAR, "a -b"), [2]);
+}
+
+#[test]
+fn nothing_is_skipped_without_the_rules() {
+    let grammar = "list = item ~ (\",\" ~ item)*\nitem = @{ ASCII_ALPHA+ }\n";
+    assert_eq!(errors(grammar, "a,b"), []);
+    assert_eq!(errors(grammar, "a, b").first(), Some(&2));
+}
+
+#[test]
+fn silent_whitespace_is_not_a_leaf() {
+    let grammar =
+        "WHITESPACE = _{ \" \" }\nlist = item ~ (\",\" ~ item)*\nitem = @{ ASCII_ALPHA+ }\n";
+    let analyzer = Analyzer::new(grammar, "list").unwrap();
+    let input = "a , b";
+    let parsed = analyzer.parse(input);
+    assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+    let mut found = Vec::new();
+    tokens(&parsed.token, input, &mut found);
+    assert!(found.iter().all(|(rule, _)| *rule != "WHITESPACE"));
+}